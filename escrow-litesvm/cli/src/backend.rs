@@ -0,0 +1,165 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context, Result};
+use litesvm::LiteSVM;
+use solana_account::Account;
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+/// Where transactions are sent and accounts are read from
+pub trait Backend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>>;
+
+    fn program_accounts(&self, program_id: &Pubkey) -> Result<Vec<(Pubkey, Account)>>;
+
+    /// Sends the instructions in one transaction paid by the first signer
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<String>;
+
+    /// Flushes any local state, a no-op for RPC
+    fn save(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct RpcBackend {
+    client: RpcClient,
+}
+
+impl RpcBackend {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: RpcClient::new(url.to_string()),
+        }
+    }
+}
+
+impl Backend for RpcBackend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        let response = self
+            .client
+            .get_account_with_commitment(address, self.client.commitment())?;
+        Ok(response.value)
+    }
+
+    fn program_accounts(&self, program_id: &Pubkey) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self.client.get_program_accounts(program_id)?)
+    }
+
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<String> {
+        let payer = signers.first().ok_or(anyhow!("a transaction needs a payer"))?;
+        let message = Message::new(instructions, Some(&payer.pubkey()));
+        let transaction =
+            Transaction::new(signers, message, self.client.get_latest_blockhash()?);
+
+        Ok(self
+            .client
+            .send_and_confirm_transaction(&transaction)?
+            .to_string())
+    }
+}
+
+/// Offline LiteSVM instance whose accounts are persisted to a local file between runs
+pub struct SandboxBackend {
+    svm: LiteSVM,
+    state_path: PathBuf,
+    // Every account a sandbox transaction has touched, the only ones worth persisting
+    known: BTreeSet<Pubkey>,
+}
+
+impl SandboxBackend {
+    pub fn load(state_path: PathBuf, program_id: Pubkey, program_so: &PathBuf) -> Result<Self> {
+        let mut svm = LiteSVM::new();
+
+        let program_data = fs::read(program_so)
+            .with_context(|| format!("failed to read program SO file {}", program_so.display()))?;
+        svm.add_program(program_id, &program_data);
+
+        let mut known = BTreeSet::new();
+        if state_path.exists() {
+            let saved: BTreeMap<Pubkey, Account> = bincode::deserialize(&fs::read(&state_path)?)
+                .with_context(|| format!("corrupt sandbox state {}", state_path.display()))?;
+
+            for (address, account) in saved {
+                svm.set_account(address, account)
+                    .map_err(|e| anyhow!("failed to restore {address}: {e:?}"))?;
+                known.insert(address);
+            }
+        }
+
+        Ok(Self {
+            svm,
+            state_path,
+            known,
+        })
+    }
+
+    pub fn airdrop(&mut self, address: &Pubkey, lamports: u64) -> Result<()> {
+        self.svm
+            .airdrop(address, lamports)
+            .map_err(|e| anyhow!("airdrop failed: {:?}", e.err))?;
+        self.known.insert(*address);
+        Ok(())
+    }
+
+    pub fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> u64 {
+        self.svm.minimum_balance_for_rent_exemption(data_len)
+    }
+}
+
+impl Backend for SandboxBackend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        Ok(self
+            .svm
+            .get_account(address)
+            .filter(|account| account.lamports > 0))
+    }
+
+    fn program_accounts(&self, program_id: &Pubkey) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self
+            .known
+            .iter()
+            .filter_map(|address| Some((*address, self.get_account(address).ok()??)))
+            .filter(|(_, account)| account.owner == *program_id)
+            .collect())
+    }
+
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<String> {
+        let payer = signers.first().ok_or(anyhow!("a transaction needs a payer"))?;
+        let message = Message::new(instructions, Some(&payer.pubkey()));
+        self.known.extend(message.account_keys.iter().copied());
+
+        let transaction = Transaction::new(signers, message, self.svm.latest_blockhash());
+        let result = self.svm.send_transaction(transaction).map_err(|failed| {
+            anyhow!(
+                "transaction failed: {:?}\n{}",
+                failed.err,
+                failed.meta.logs.join("\n")
+            )
+        })?;
+        // Move to a fresh blockhash so an identical follow-up transaction is not a duplicate
+        self.svm.expire_blockhash();
+
+        Ok(result.signature.to_string())
+    }
+
+    fn save(&self) -> Result<()> {
+        let accounts: BTreeMap<Pubkey, Account> = self
+            .known
+            .iter()
+            .filter_map(|address| Some((*address, self.get_account(address).ok()??)))
+            .filter(|(_, account)| !account.executable)
+            .collect();
+
+        fs::write(&self.state_path, bincode::serialize(&accounts)?)
+            .with_context(|| format!("failed to write {}", self.state_path.display()))
+    }
+}
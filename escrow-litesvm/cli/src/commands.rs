@@ -0,0 +1,357 @@
+use anchor_escrow::state::Escrow;
+use anchor_lang::{
+    solana_program::program_pack::Pack, AccountDeserialize, Discriminator, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id,
+        spl_associated_token_account::{self, instruction::create_associated_token_account_idempotent},
+    },
+    token::spl_token,
+    token_interface::{spl_token_2022, TokenAccount},
+};
+use anyhow::{anyhow, bail, Result};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_system_interface::instruction as system_instruction;
+
+use crate::backend::{Backend, SandboxBackend};
+
+const SANDBOX_AIRDROP: u64 = 10_000_000_000;
+
+pub fn escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &anchor_escrow::ID,
+    )
+    .0
+}
+
+fn fetch_escrow(backend: &dyn Backend, address: &Pubkey) -> Result<Escrow> {
+    let account = backend
+        .get_account(address)?
+        .ok_or(anyhow!("escrow {address} does not exist"))?;
+    Ok(Escrow::try_deserialize(&mut account.data.as_ref())?)
+}
+
+// The token program owning the mint decides which program every ATA lives under
+fn token_program_of(backend: &dyn Backend, mint: &Pubkey) -> Result<Pubkey> {
+    let owner = backend
+        .get_account(mint)?
+        .ok_or(anyhow!("mint {mint} does not exist"))?
+        .owner;
+
+    if owner != spl_token::ID && owner != spl_token_2022::ID {
+        bail!("{mint} is not owned by a token program");
+    }
+    Ok(owner)
+}
+
+fn token_balance(backend: &dyn Backend, address: &Pubkey) -> Result<Option<u64>> {
+    backend
+        .get_account(address)?
+        .map(|account| Ok(TokenAccount::try_deserialize(&mut account.data.as_ref())?.amount))
+        .transpose()
+}
+
+fn print_escrow(backend: &dyn Backend, address: &Pubkey, escrow: &Escrow) -> Result<()> {
+    println!("Escrow      {address}");
+    println!("  seed      {}", escrow.seed);
+    println!("  maker     {}", escrow.maker);
+    println!("  mint A    {}", escrow.mint_a);
+    if escrow.is_collection_offer() {
+        println!("  wants     any NFT of collection {}", escrow.collection);
+    } else {
+        println!("  mint B    {}", escrow.mint_b);
+    }
+    println!("  receive   {}", escrow.receive);
+    if escrow.manager != Pubkey::default() {
+        println!("  manager   {}", escrow.manager);
+    }
+
+    let token_program = token_program_of(backend, &escrow.mint_a)?;
+    let vault = get_associated_token_address_with_program_id(address, &escrow.mint_a, &token_program);
+    match token_balance(backend, &vault)? {
+        Some(amount) => println!("  vault     {vault} ({amount})"),
+        None => println!("  vault     {vault} (closed)"),
+    }
+
+    Ok(())
+}
+
+pub fn make(
+    backend: &mut dyn Backend,
+    payer: &Keypair,
+    seed: u64,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    deposit: u64,
+    receive: u64,
+) -> Result<()> {
+    let maker = payer.pubkey();
+    let token_program = token_program_of(backend, &mint_a)?;
+    let escrow = escrow_address(&maker, seed);
+
+    let make_ix = Instruction {
+        program_id: anchor_escrow::ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a: get_associated_token_address_with_program_id(&maker, &mint_a, &token_program),
+            escrow,
+            vault: get_associated_token_address_with_program_id(&escrow, &mint_a, &token_program),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program,
+            system_program: solana_system_interface::program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_escrow::instruction::Make {
+            seed,
+            deposit,
+            receive,
+        }
+        .data(),
+    };
+
+    let signature = backend.send(&[make_ix], &[payer])?;
+    println!("Made escrow {escrow}\nSignature {signature}");
+
+    Ok(())
+}
+
+pub fn take(
+    backend: &mut dyn Backend,
+    payer: &Keypair,
+    maker: Pubkey,
+    seed: u64,
+    nft: Option<Pubkey>,
+) -> Result<()> {
+    let taker = payer.pubkey();
+    let escrow_key = escrow_address(&maker, seed);
+    let escrow = fetch_escrow(backend, &escrow_key)?;
+
+    let (mint_b, collection) = match (escrow.is_collection_offer(), nft) {
+        (true, Some(nft)) => (nft, Some(escrow.collection)),
+        (true, None) => bail!("escrow {escrow_key} is a collection offer, pass --nft"),
+        (false, _) => (escrow.mint_b, None),
+    };
+
+    // Each mint's ATAs live under its own token program
+    let token_program = token_program_of(backend, &escrow.mint_a)?;
+    let token_program_b = token_program_of(backend, &mint_b)?;
+    let ata_a = |owner: &Pubkey| {
+        get_associated_token_address_with_program_id(owner, &escrow.mint_a, &token_program)
+    };
+    let ata_b = |owner: &Pubkey| {
+        get_associated_token_address_with_program_id(owner, &mint_b, &token_program_b)
+    };
+
+    let take_ix = Instruction {
+        program_id: anchor_escrow::ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker,
+            maker,
+            mint_a: escrow.mint_a,
+            mint_b,
+            collection,
+            taker_ata_a: ata_a(&taker),
+            taker_ata_b: ata_b(&taker),
+            maker_ata_b: ata_b(&maker),
+            escrow: escrow_key,
+            vault: ata_a(&escrow_key),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program,
+            token_program_b,
+            system_program: solana_system_interface::program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_escrow::instruction::Take.data(),
+    };
+
+    let signature = backend.send(&[take_ix], &[payer])?;
+    println!("Took escrow {escrow_key}\nSignature {signature}");
+
+    Ok(())
+}
+
+pub fn refund(
+    backend: &mut dyn Backend,
+    payer: &Keypair,
+    maker: Option<Pubkey>,
+    seed: u64,
+) -> Result<()> {
+    let maker = maker.unwrap_or(payer.pubkey());
+    let escrow_key = escrow_address(&maker, seed);
+    let escrow = fetch_escrow(backend, &escrow_key)?;
+    let token_program = token_program_of(backend, &escrow.mint_a)?;
+
+    let refund_ix = Instruction {
+        program_id: anchor_escrow::ID,
+        accounts: anchor_escrow::accounts::Refund {
+            authority: payer.pubkey(),
+            maker,
+            mint_a: escrow.mint_a,
+            maker_ata_a: get_associated_token_address_with_program_id(&maker, &escrow.mint_a, &token_program),
+            escrow: escrow_key,
+            vault: get_associated_token_address_with_program_id(&escrow_key, &escrow.mint_a, &token_program),
+            token_program,
+            system_program: solana_system_interface::program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_escrow::instruction::Refund.data(),
+    };
+
+    let signature = backend.send(&[refund_ix], &[payer])?;
+    println!("Refunded escrow {escrow_key}\nSignature {signature}");
+
+    Ok(())
+}
+
+pub fn list(backend: &mut dyn Backend, maker: Option<Pubkey>) -> Result<()> {
+    let mut escrows: Vec<(Pubkey, Escrow)> = backend
+        .program_accounts(&anchor_escrow::ID)?
+        .into_iter()
+        .filter(|(_, account)| account.data.starts_with(Escrow::DISCRIMINATOR))
+        .filter_map(|(address, account)| {
+            Some((address, Escrow::try_deserialize(&mut account.data.as_ref()).ok()?))
+        })
+        .filter(|(_, escrow)| maker.is_none_or(|maker| escrow.maker == maker))
+        .collect();
+    escrows.sort_by_key(|(_, escrow)| (escrow.maker, escrow.seed));
+
+    if escrows.is_empty() {
+        println!("No open escrows");
+    }
+    for (address, escrow) in &escrows {
+        print_escrow(backend, address, escrow)?;
+    }
+
+    Ok(())
+}
+
+pub fn show(backend: &mut dyn Backend, maker: Pubkey, seed: u64) -> Result<()> {
+    let address = escrow_address(&maker, seed);
+    let escrow = fetch_escrow(backend, &address)?;
+    print_escrow(backend, &address, &escrow)
+}
+
+// Sandbox transactions are free, keep the signer funded so every command can pay fees and rent
+pub fn fund_sandbox_payer(sandbox: &mut SandboxBackend, payer: &Keypair) -> Result<()> {
+    let balance = sandbox
+        .get_account(&payer.pubkey())?
+        .map_or(0, |account| account.lamports);
+
+    if balance < SANDBOX_AIRDROP / 2 {
+        sandbox.airdrop(&payer.pubkey(), SANDBOX_AIRDROP)?;
+    }
+    Ok(())
+}
+
+pub fn create_mint(
+    sandbox: &mut SandboxBackend,
+    payer: &Keypair,
+    decimals: u8,
+    amount: u64,
+    token_2022: bool,
+) -> Result<Pubkey> {
+    let token_program = if token_2022 {
+        spl_token_2022::ID
+    } else {
+        spl_token::ID
+    };
+    let mint = Keypair::new();
+    let owner = payer.pubkey();
+    let ata = get_associated_token_address_with_program_id(&owner, &mint.pubkey(), &token_program);
+    let space = spl_token_2022::state::Mint::LEN;
+
+    let instructions = [
+        system_instruction::create_account(
+            &owner,
+            &mint.pubkey(),
+            sandbox.minimum_balance_for_rent_exemption(space),
+            space as u64,
+            &token_program,
+        ),
+        spl_token_2022::instruction::initialize_mint2(
+            &token_program,
+            &mint.pubkey(),
+            &owner,
+            None,
+            decimals,
+        )?,
+        create_associated_token_account_idempotent(&owner, &owner, &mint.pubkey(), &token_program),
+        spl_token_2022::instruction::mint_to_checked(
+            &token_program,
+            &mint.pubkey(),
+            &ata,
+            &owner,
+            &[],
+            amount,
+            decimals,
+        )?,
+    ];
+
+    let signature = sandbox.send(&instructions, &[payer, &mint])?;
+    println!("Created mint {} with {amount} tokens in {ata}\nSignature {signature}", mint.pubkey());
+
+    Ok(mint.pubkey())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::*;
+
+    fn sandbox(state_path: &Path) -> SandboxBackend {
+        let program_so =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/deploy/anchor_escrow.so");
+        SandboxBackend::load(state_path.to_path_buf(), anchor_escrow::ID, &program_so).unwrap()
+    }
+
+    fn balance(backend: &dyn Backend, owner: &Pubkey, mint: &Pubkey) -> Option<u64> {
+        let ata = get_associated_token_address_with_program_id(owner, mint, &spl_token::ID);
+        token_balance(backend, &ata).unwrap()
+    }
+
+    #[test]
+    fn test_sandbox_make_take_refund() {
+        let state_path = std::env::temp_dir().join(format!("escrow-cli-{}.bin", Pubkey::new_unique()));
+        let maker = Keypair::new();
+        let taker = Keypair::new();
+
+        let mut backend = sandbox(&state_path);
+        fund_sandbox_payer(&mut backend, &maker).unwrap();
+        fund_sandbox_payer(&mut backend, &taker).unwrap();
+        let mint_a = create_mint(&mut backend, &maker, 6, 1_000, false).unwrap();
+        let mint_b = create_mint(&mut backend, &taker, 6, 1_000, false).unwrap();
+
+        make(&mut backend, &maker, 1, mint_a, mint_b, 100, 200).unwrap();
+        make(&mut backend, &maker, 2, mint_a, mint_b, 50, 10).unwrap();
+        backend.save().unwrap();
+
+        // Escrows made in one run are taken and refunded in the next
+        let mut backend = sandbox(&state_path);
+        take(&mut backend, &taker, maker.pubkey(), 1, None).unwrap();
+        assert_eq!(balance(&backend, &taker.pubkey(), &mint_a), Some(100));
+        assert_eq!(balance(&backend, &maker.pubkey(), &mint_b), Some(200));
+        assert!(backend.get_account(&escrow_address(&maker.pubkey(), 1)).unwrap().is_none());
+
+        refund(&mut backend, &maker, None, 2).unwrap();
+        assert_eq!(balance(&backend, &maker.pubkey(), &mint_a), Some(900));
+        assert!(backend.get_account(&escrow_address(&maker.pubkey(), 2)).unwrap().is_none());
+
+        // Nothing is left to take or refund
+        assert!(take(&mut backend, &taker, maker.pubkey(), 2, None).is_err());
+        assert!(refund(&mut backend, &maker, None, 1).is_err());
+
+        fs::remove_file(&state_path).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+//! Command-line tool for the anchor-escrow program
+//!
+//! Every command runs either against a cluster over RPC (`--url`) or against an
+//! offline LiteSVM sandbox (`--sandbox <state file>`) that loads `anchor_escrow.so`
+//! and persists the accounts it touched, so flows can be rehearsed without a validator.
+
+mod backend;
+mod commands;
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_pubkey::Pubkey;
+
+use backend::{Backend, RpcBackend, SandboxBackend};
+
+#[derive(Parser)]
+#[command(name = "escrow-cli", version, about = "Make, take, refund and inspect escrows")]
+struct Cli {
+    /// Keypair paying for and signing transactions
+    #[arg(long, short, global = true, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+
+    /// RPC URL of the cluster to target
+    #[arg(long, short, global = true, default_value = "http://127.0.0.1:8899", conflicts_with = "sandbox")]
+    url: String,
+
+    /// Run against a local LiteSVM sandbox persisted to this file instead of a cluster
+    #[arg(long, global = true)]
+    sandbox: Option<PathBuf>,
+
+    /// Program binary loaded into the sandbox
+    #[arg(long, global = true, default_value = "target/deploy/anchor_escrow.so")]
+    program_so: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Deposit mint A into a new escrow asking for mint B
+    Make {
+        #[arg(long)]
+        seed: u64,
+        #[arg(long)]
+        mint_a: Pubkey,
+        #[arg(long)]
+        mint_b: Pubkey,
+        /// Amount of mint A deposited, in base units
+        #[arg(long)]
+        deposit: u64,
+        /// Amount of mint B asked for, in base units
+        #[arg(long)]
+        receive: u64,
+    },
+    /// Pay an escrow's mint B and receive its mint A
+    Take {
+        #[arg(long)]
+        maker: Pubkey,
+        #[arg(long)]
+        seed: u64,
+        /// NFT paid into a collection offer
+        #[arg(long)]
+        nft: Option<Pubkey>,
+    },
+    /// Return the deposit to the maker and close the escrow
+    Refund {
+        /// Maker of the escrow, defaults to the signer (set it when refunding as manager)
+        #[arg(long)]
+        maker: Option<Pubkey>,
+        #[arg(long)]
+        seed: u64,
+    },
+    /// List open escrows
+    List {
+        #[arg(long)]
+        maker: Option<Pubkey>,
+    },
+    /// Show one escrow and its vault balance
+    Show {
+        #[arg(long)]
+        maker: Pubkey,
+        #[arg(long)]
+        seed: u64,
+    },
+    /// Sandbox only: create a mint and mint tokens to the signer
+    CreateMint {
+        #[arg(long, default_value_t = 6)]
+        decimals: u8,
+        #[arg(long)]
+        amount: u64,
+        /// Create the mint under Token-2022 instead of the token program
+        #[arg(long)]
+        token_2022: bool,
+    },
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let keypair_path = expand_home(&cli.keypair);
+    let payer: Keypair = read_keypair_file(&keypair_path)
+        .map_err(|e| anyhow!("failed to read keypair {}: {e}", keypair_path.display()))?;
+
+    match cli.sandbox {
+        Some(state_path) => {
+            let mut sandbox =
+                SandboxBackend::load(state_path, anchor_escrow::ID, &cli.program_so)?;
+            commands::fund_sandbox_payer(&mut sandbox, &payer)?;
+
+            if let Command::CreateMint {
+                decimals,
+                amount,
+                token_2022,
+            } = cli.command
+            {
+                commands::create_mint(&mut sandbox, &payer, decimals, amount, token_2022)?;
+            } else {
+                run(&mut sandbox, &payer, cli.command)?;
+            }
+
+            sandbox.save()
+        }
+        None => {
+            let mut rpc = RpcBackend::new(&cli.url);
+            run(&mut rpc, &payer, cli.command)
+        }
+    }
+}
+
+fn run(backend: &mut dyn Backend, payer: &Keypair, command: Command) -> Result<()> {
+    match command {
+        Command::Make {
+            seed,
+            mint_a,
+            mint_b,
+            deposit,
+            receive,
+        } => commands::make(backend, payer, seed, mint_a, mint_b, deposit, receive),
+        Command::Take { maker, seed, nft } => commands::take(backend, payer, maker, seed, nft),
+        Command::Refund { maker, seed } => commands::refund(backend, payer, maker, seed),
+        Command::List { maker } => commands::list(backend, maker),
+        Command::Show { maker, seed } => commands::show(backend, maker, seed),
+        Command::CreateMint { .. } => Err(anyhow!("create-mint is only available with --sandbox")),
+    }
+}
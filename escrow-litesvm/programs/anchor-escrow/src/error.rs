@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("An NFT can only be escrowed or requested as a single unit")]
+    InvalidNftAmount,
+    #[msg("The provided mint is not an NFT (decimals 0, supply 1)")]
+    NotAnNft,
+    #[msg("The collection mint does not hold its own Token-2022 token group")]
+    NotACollection,
+    #[msg("The NFT is not a member of the requested collection's token group")]
+    CollectionMismatch,
+    #[msg("The collection mint account is required for collection offers")]
+    MissingCollection,
+    #[msg("The provided mint does not match the mint requested by the escrow")]
+    WrongMintB,
+    #[msg("A basket needs between one and MAX_BASKET_LEGS legs on each side")]
+    InvalidBasketSize,
+    #[msg("A basket leg must move a non-zero amount")]
+    ZeroLegAmount,
+    #[msg("The same mint appears twice on one side of the basket")]
+    DuplicateBasketMint,
+    #[msg("Remaining accounts do not match the basket legs")]
+    BasketAccountsMismatch,
+    #[msg("Only the maker or the escrow manager can do this")]
+    NotMakerOrManager,
+    #[msg("The vault program is not supported for deposits on take")]
+    UnsupportedVault,
+    #[msg("Remaining accounts do not match the vault deposit")]
+    VaultAccountsMismatch,
+    #[msg("The account is not an escrow in the pre-collection, pre-manager layout")]
+    NotALegacyEscrow,
+    #[msg("Basket legs can't use a mint with a transfer hook")]
+    TransferHookLeg,
+}
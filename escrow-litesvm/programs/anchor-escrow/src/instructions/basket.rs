@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, Create},
+    token::ID as TOKEN_PROGRAM_ID,
+    token_2022::ID as TOKEN_2022_PROGRAM_ID,
+    token_interface::{
+        spl_token_2022::{
+            extension::{transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions},
+            state::Mint as MintState,
+        },
+        transfer_checked, Mint, TokenAccount, TransferChecked,
+    },
+};
+
+use crate::{
+    error::EscrowError,
+    state::{BasketLeg, MAX_BASKET_LEGS},
+};
+
+// Every basket leg is passed through remaining_accounts as a fixed-size group,
+// starting with [mint, token_program] so legs can mix SPL Token and Token-2022
+pub const ACCOUNTS_PER_LEG: usize = 4;
+
+pub fn validate_legs(legs: &[BasketLeg]) -> Result<()> {
+    require!(
+        !legs.is_empty() && legs.len() <= MAX_BASKET_LEGS,
+        EscrowError::InvalidBasketSize
+    );
+
+    for (i, leg) in legs.iter().enumerate() {
+        require!(leg.amount > 0, EscrowError::ZeroLegAmount);
+        require!(
+            legs[..i].iter().all(|other| other.mint != leg.mint),
+            EscrowError::DuplicateBasketMint
+        );
+    }
+
+    Ok(())
+}
+
+pub fn leg_accounts<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    index: usize,
+) -> Result<&'a [AccountInfo<'info>]> {
+    let start = index * ACCOUNTS_PER_LEG;
+    remaining_accounts
+        .get(start..start + ACCOUNTS_PER_LEG)
+        .ok_or(error!(EscrowError::BasketAccountsMismatch))
+}
+
+// Makes sure the mint account is the one recorded for the leg and the token program owns it
+pub fn require_leg_mint(mint: &AccountInfo, leg: &BasketLeg, token_program: &AccountInfo) -> Result<()> {
+    require_keys_eq!(mint.key(), leg.mint, EscrowError::BasketAccountsMismatch);
+    require!(
+        *token_program.key == TOKEN_PROGRAM_ID || *token_program.key == TOKEN_2022_PROGRAM_ID,
+        EscrowError::BasketAccountsMismatch
+    );
+    require_keys_eq!(*mint.owner, *token_program.key, EscrowError::BasketAccountsMismatch);
+    Ok(())
+}
+
+// Makes sure a requested mint is the one recorded for the leg, only the mint is passed for it on make
+pub fn require_requested_mint(mint: &AccountInfo, leg: &BasketLeg) -> Result<()> {
+    require_keys_eq!(mint.key(), leg.mint, EscrowError::BasketAccountsMismatch);
+    require!(
+        *mint.owner == TOKEN_PROGRAM_ID || *mint.owner == TOKEN_2022_PROGRAM_ID,
+        EscrowError::BasketAccountsMismatch
+    );
+    Ok(())
+}
+
+// Legs move with a plain transfer_checked, a hook mint would fail to settle or refund
+// The hook program can be set after make, so the extension is refused even without one
+pub fn require_no_transfer_hook(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != TOKEN_2022_PROGRAM_ID {
+        return Ok(());
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    require!(
+        state.get_extension::<TransferHook>().is_err(),
+        EscrowError::TransferHookLeg
+    );
+    Ok(())
+}
+
+// Whatever the vault holds, leg amount plus anything sent to it afterwards
+pub fn vault_amount(vault: &AccountInfo) -> Result<u64> {
+    Ok(TokenAccount::try_deserialize(&mut &vault.try_borrow_data()?[..])?.amount)
+}
+
+pub fn require_ata(
+    account: &AccountInfo,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<()> {
+    require_keys_eq!(
+        account.key(),
+        get_associated_token_address_with_program_id(authority, mint, token_program),
+        EscrowError::BasketAccountsMismatch
+    );
+    Ok(())
+}
+
+pub struct LegCpi<'a, 'info> {
+    pub payer: &'a AccountInfo<'info>,
+    pub associated_token_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> LegCpi<'a, 'info> {
+    // Creates the ATA unless it already exists
+    pub fn create_ata(
+        &self,
+        token_program: &AccountInfo<'info>,
+        account: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+    ) -> Result<()> {
+        require_ata(account, authority.key, mint.key, token_program.key)?;
+
+        let cpi_accounts = Create {
+            payer: self.payer.clone(),
+            associated_token: account.clone(),
+            authority: authority.clone(),
+            mint: mint.clone(),
+            system_program: self.system_program.clone(),
+            token_program: token_program.clone(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.associated_token_program.clone(), cpi_accounts);
+
+        create_idempotent(cpi_ctx)
+    }
+}
+
+// Moves `amount` of a leg's mint with the leg's own token program
+pub fn transfer_leg<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let decimals = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?.decimals;
+
+    let cpi_accounts = TransferChecked {
+        from: from.clone(),
+        to: to.clone(),
+        authority: authority.clone(),
+        mint: mint.clone(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.clone(),
+        cpi_accounts,
+        signer_seeds,
+    );
+
+    transfer_checked(cpi_ctx, amount, decimals)
+}
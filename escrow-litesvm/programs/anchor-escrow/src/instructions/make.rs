@@ -1,7 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, spl_token_2022::onchain::invoke_transfer_checked}};
 
-use crate::state::Escrow;
+use crate::{
+    error::EscrowError,
+    nft::{is_nft, verify_collection},
+    state::Escrow,
+};
 
 #[derive(Accounts)]
 #[instruction(seed: u64)]
@@ -14,6 +18,7 @@ pub struct Make<'info> {
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
+        associated_token::token_program = token_program,
     )]
     pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
     #[account(
@@ -29,6 +34,7 @@ pub struct Make<'info> {
         payer = maker,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
+        associated_token::token_program = token_program,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -43,26 +49,54 @@ impl<'info> Make<'info> {
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
+            collection: Pubkey::default(),
+            manager: Pubkey::default(),
             receive,
             bump: bumps.escrow,
         });
 
+        if is_nft(&self.mint_b) {
+            require!(receive == 1, EscrowError::InvalidNftAmount);
+        }
+
         Ok(())
     }
 
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
+    // mint_b is the collection mint here; any member of its token group settles the offer
+    pub fn init_collection_escrow(&mut self, seed: u64, bumps: &MakeBumps) -> Result<()> {
+        verify_collection(&self.mint_b.to_account_info())?;
+
+        self.escrow.set_inner(Escrow {
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: Pubkey::default(),
+            collection: self.mint_b.key(),
+            manager: Pubkey::default(),
+            receive: 1,
+            bump: bumps.escrow,
+        });
 
-        let cpi_accounts = TransferChecked {
-            from: self.maker_ata_a.to_account_info(),
-            to: self.vault.to_account_info(),
-            authority: self.maker.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-        };
+        Ok(())
+    }
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    // hook_accounts resolve mint A's transfer hook accounts, empty for plain mints
+    pub fn deposit(&mut self, deposit: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if is_nft(&self.mint_a) {
+            require!(deposit == 1, EscrowError::InvalidNftAmount);
+        }
 
-        transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)?;
+        invoke_transfer_checked(
+            &self.token_program.key(),
+            self.maker_ata_a.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+            self.maker.to_account_info(),
+            hook_accounts,
+            deposit,
+            self.mint_a.decimals,
+            &[],
+        )?;
 
         Ok(())
     }
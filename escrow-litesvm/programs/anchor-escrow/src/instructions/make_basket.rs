@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::{
+    error::EscrowError,
+    instructions::basket::*,
+    state::{BasketEscrow, BasketLeg},
+};
+
+// Remaining accounts, per offered leg: [mint, token_program, maker_ata, vault]
+// then the mint of every requested leg
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBasket<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"basket", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + BasketEscrow::INIT_SPACE,
+    )]
+    pub basket: Account<'info, BasketEscrow>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeBasket<'info> {
+    pub fn init_basket(
+        &mut self,
+        seed: u64,
+        offered: Vec<BasketLeg>,
+        requested: Vec<BasketLeg>,
+        bumps: &MakeBasketBumps,
+    ) -> Result<()> {
+        validate_legs(&offered)?;
+        validate_legs(&requested)?;
+
+        self.basket.set_inner(BasketEscrow {
+            seed,
+            maker: self.maker.key(),
+            offered,
+            requested,
+            bump: bumps.basket,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_legs(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let offered = self.basket.offered.clone();
+        let requested = &self.basket.requested;
+        require!(
+            remaining_accounts.len() == offered.len() * ACCOUNTS_PER_LEG + requested.len(),
+            EscrowError::BasketAccountsMismatch
+        );
+        let (offered_accounts, requested_mints) =
+            remaining_accounts.split_at(offered.len() * ACCOUNTS_PER_LEG);
+
+        // Refused before anything is deposited, a hook mint could never be settled
+        for (mint, leg) in requested_mints.iter().zip(requested) {
+            require_requested_mint(mint, leg)?;
+            require_no_transfer_hook(mint)?;
+        }
+
+        let maker = self.maker.to_account_info();
+        let basket = self.basket.to_account_info();
+        let cpi = LegCpi {
+            payer: &maker,
+            associated_token_program: &self.associated_token_program.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+        };
+
+        for (i, leg) in offered.iter().enumerate() {
+            let [mint, token_program, maker_ata, vault] = leg_accounts(offered_accounts, i)? else {
+                return err!(EscrowError::BasketAccountsMismatch);
+            };
+            require_leg_mint(mint, leg, token_program)?;
+            require_no_transfer_hook(mint)?;
+
+            require_ata(maker_ata, maker.key, &leg.mint, token_program.key)?;
+            cpi.create_ata(token_program, vault, &basket, mint)?;
+            transfer_leg(token_program, maker_ata, vault, &maker, mint, leg.amount, &[])?;
+        }
+
+        Ok(())
+    }
+}
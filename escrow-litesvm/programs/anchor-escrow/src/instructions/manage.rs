@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{error::EscrowError, nft::is_nft, state::Escrow};
+
+#[derive(Accounts)]
+pub struct SetManager<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> SetManager<'info> {
+    // Pubkey::default() removes the manager
+    pub fn set_manager(&mut self, manager: Pubkey) -> Result<()> {
+        self.escrow.manager = manager;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Amend<'info> {
+    #[account(constraint = escrow.can_manage(&authority.key()) @ EscrowError::NotMakerOrManager)]
+    pub authority: Signer<'info>,
+    // The requested mint, or the collection mint for collection offers
+    #[account(constraint = escrow.accepts_mint_b(&mint_b.key()) @ EscrowError::WrongMintB)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> Amend<'info> {
+    pub fn amend_receive(&mut self, receive: u64) -> Result<()> {
+        if self.escrow.is_collection_offer() || is_nft(&self.mint_b) {
+            require!(receive == 1, EscrowError::InvalidNftAmount);
+        }
+
+        self.escrow.receive = receive;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
+
+use crate::{error::EscrowError, state::Escrow};
+
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: A legacy escrow doesn't deserialize as Escrow, its length and discriminator are checked instead
+    #[account(mut, owner = crate::ID @ EscrowError::NotALegacyEscrow)]
+    pub escrow: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateEscrow<'info> {
+    // The appended fields start zeroed, so the escrow has no collection and no manager
+    pub fn migrate(&mut self) -> Result<()> {
+        let escrow = self.escrow.to_account_info();
+        require!(
+            escrow.data_len() == Escrow::LEGACY_LEN
+                && escrow.try_borrow_data()?.starts_with(Escrow::DISCRIMINATOR),
+            EscrowError::NotALegacyEscrow
+        );
+
+        // The payer covers the rent of the new fields
+        let space = 8 + Escrow::INIT_SPACE;
+        let top_up = Rent::get()?.minimum_balance(space).saturating_sub(escrow.lamports());
+        if top_up > 0 {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.payer.to_account_info(),
+                        to: escrow.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+
+        escrow.realloc(space, true)?;
+
+        Ok(())
+    }
+}
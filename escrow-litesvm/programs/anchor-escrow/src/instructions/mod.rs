@@ -1,7 +1,18 @@
+pub mod basket;
 pub mod make;
+pub mod make_basket;
+pub mod manage;
+pub mod migrate_escrow;
 pub mod refund;
+pub mod refund_basket;
 pub mod take;
+pub mod take_basket;
 
 pub use make::*;
+pub use make_basket::*;
+pub use manage::*;
+pub use migrate_escrow::*;
 pub use refund::*;
-pub use take::*;
\ No newline at end of file
+pub use refund_basket::*;
+pub use take::*;
+pub use take_basket::*;
@@ -1,17 +1,24 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, CloseAccount, close_account, spl_token_2022::onchain::invoke_transfer_checked};
 
-use crate::state::Escrow;
+use crate::{error::EscrowError, state::Escrow};
 
 #[derive(Accounts)]
 pub struct Refund<'info> {
+    // Either the maker or the manager, funds always go back to the maker
+    #[account(
+        mut,
+        constraint = escrow.can_manage(&authority.key()) @ EscrowError::NotMakerOrManager,
+    )]
+    authority: Signer<'info>,
     #[account(mut)]
-    maker: Signer<'info>,
+    maker: SystemAccount<'info>,
     mint_a: InterfaceAccount<'info, Mint>,
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
+        associated_token::token_program = token_program,
     )]
     maker_ata_a: InterfaceAccount<'info, TokenAccount>,
     #[account(
@@ -27,6 +34,7 @@ pub struct Refund<'info> {
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
+        associated_token::token_program = token_program,
     )]
     vault: InterfaceAccount<'info, TokenAccount>,
     token_program: Interface<'info, TokenInterface>,
@@ -34,7 +42,7 @@ pub struct Refund<'info> {
 }
 
 impl<'info> Refund<'info> {
-    pub fn refund_and_close_vault(&mut self) -> Result<()> {
+    pub fn refund_and_close_vault(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.key.as_ref(),
@@ -42,18 +50,17 @@ impl<'info> Refund<'info> {
             &[self.escrow.bump]
         ]];
         
-        let cpi_program = self.token_program.to_account_info();
-
-        let cpi_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
-
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
-
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+        invoke_transfer_checked(
+            &self.token_program.key(),
+            self.vault.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.maker_ata_a.to_account_info(),
+            self.escrow.to_account_info(),
+            hook_accounts,
+            self.vault.amount,
+            self.mint_a.decimals,
+            &signer_seeds,
+        )?;
 
         let cpi_program = self.token_program.to_account_info();
 
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{close_account, CloseAccount},
+};
+
+use crate::{error::EscrowError, instructions::basket::*, state::BasketEscrow};
+
+// Remaining accounts, per offered leg: [mint, token_program, vault, maker_ata]
+#[derive(Accounts)]
+pub struct RefundBasket<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump,
+    )]
+    pub basket: Account<'info, BasketEscrow>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundBasket<'info> {
+    pub fn refund_and_close_vaults(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let offered = self.basket.offered.clone();
+        require!(
+            remaining_accounts.len() == offered.len() * ACCOUNTS_PER_LEG,
+            EscrowError::BasketAccountsMismatch
+        );
+
+        let maker = self.maker.to_account_info();
+        let basket = self.basket.to_account_info();
+        let cpi = LegCpi {
+            payer: &maker,
+            associated_token_program: &self.associated_token_program.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+        };
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump],
+        ]];
+
+        for (i, leg) in offered.iter().enumerate() {
+            let [mint, token_program, vault, maker_ata] = leg_accounts(remaining_accounts, i)? else {
+                return err!(EscrowError::BasketAccountsMismatch);
+            };
+            require_leg_mint(mint, leg, token_program)?;
+
+            require_ata(vault, basket.key, &leg.mint, token_program.key)?;
+            cpi.create_ata(token_program, maker_ata, &maker, mint)?;
+            transfer_leg(token_program, vault, maker_ata, &basket, mint, vault_amount(vault)?, &signer_seeds)?;
+
+            let cpi_accounts = CloseAccount {
+                account: vault.clone(),
+                destination: maker.clone(),
+                authority: basket.clone(),
+            };
+
+            let cpi_context =
+                CpiContext::new_with_signer(token_program.clone(), cpi_accounts, &signer_seeds);
+
+            close_account(cpi_context)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,12 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account, spl_token_2022::onchain::invoke_transfer_checked}};
 
-use crate::state::Escrow;
+use crate::{
+    error::EscrowError,
+    nft::{is_nft, verify_collection_member},
+    state::Escrow,
+    vault_interface::VaultDeposit,
+};
 
 //Create context
 #[derive(Accounts)]
@@ -11,18 +16,25 @@ pub struct Take<'info> {
     #[account(mut)]
     pub maker: SystemAccount<'info>,
     pub mint_a: InterfaceAccount<'info, Mint>,
+    // Mint B may live under the other token program than mint A
+    #[account(mint::token_program = token_program_b)]
     pub mint_b: InterfaceAccount<'info, Mint>,
+    // Only required when the escrow is a collection offer
+    #[account(address = escrow.collection @ EscrowError::CollectionMismatch)]
+    pub collection: Option<InterfaceAccount<'info, Mint>>,
     #[account(
         init_if_needed,
         payer = taker,
         associated_token::mint = mint_a,
         associated_token::authority = taker,
+        associated_token::token_program = token_program,
     )]
     pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
     #[account(
         mut,
         associated_token::mint = mint_b,
         associated_token::authority = taker,
+        associated_token::token_program = token_program_b,
     )]
     pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
     #[account(
@@ -30,6 +42,7 @@ pub struct Take<'info> {
         payer = taker,
         associated_token::mint = mint_b,
         associated_token::authority = maker,
+        associated_token::token_program = token_program_b,
     )]
     pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
     #[account(
@@ -37,7 +50,7 @@ pub struct Take<'info> {
         close = maker,
         has_one = maker,
         has_one = mint_a,
-        has_one = mint_b,
+        constraint = escrow.accepts_mint_b(&mint_b.key()) @ EscrowError::WrongMintB,
         seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
     )]
@@ -46,19 +59,40 @@ pub struct Take<'info> {
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
+        associated_token::token_program = token_program,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
+//Verify the NFT offered for a collection escrow
 //Deposit tokens from taker to maker
 //Transfer tokens from vault to taker
 //Close vault account
 impl<'info> Take<'info> {
+    pub fn verify_mint_b(&self) -> Result<()> {
+        if !self.escrow.is_collection_offer() {
+            return Ok(());
+        }
+
+        require!(is_nft(&self.mint_b), EscrowError::NotAnNft);
+
+        let collection = self
+            .collection
+            .as_ref()
+            .ok_or(EscrowError::MissingCollection)?;
+
+        verify_collection_member(
+            &self.mint_b.to_account_info(),
+            &collection.to_account_info(),
+        )
+    }
+
     pub fn deposit(&mut self) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
+        let cpi_program = self.token_program_b.to_account_info();
 
         let cpi_accounts = TransferChecked {
             from: self.taker_ata_b.to_account_info(),
@@ -72,7 +106,12 @@ impl<'info> Take<'info> {
         transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    // With a vault deposit, mint A only passes through taker_ata_a on its way into the taker's vault position
+    pub fn withdraw_and_close_vault(
+        &mut self,
+        hook_accounts: &[AccountInfo<'info>],
+        vault_deposit: Option<VaultDeposit<'_, 'info>>,
+    ) -> Result<()> {
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.key.as_ref(),
@@ -80,18 +119,24 @@ impl<'info> Take<'info> {
             &[self.escrow.bump]
         ]];
 
-        let cpi_program = self.token_program.to_account_info();
+        let amount = self.vault.amount;
 
-        let cpi_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            to: self.taker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-        };
+        // Resolves the mint's transfer hook accounts from hook_accounts, a plain transfer when there are none
+        invoke_transfer_checked(
+            &self.token_program.key(),
+            self.vault.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.taker_ata_a.to_account_info(),
+            self.escrow.to_account_info(),
+            hook_accounts,
+            amount,
+            self.mint_a.decimals,
+            &signer_seeds,
+        )?;
 
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
-
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+        if let Some(vault_deposit) = vault_deposit {
+            vault_deposit.deposit(&self.taker.key(), &self.taker_ata_a.key(), amount)?;
+        }
 
         let cpi_program = self.token_program.to_account_info();
 
@@ -105,4 +150,4 @@ impl<'info> Take<'info> {
 
         close_account(cpi_context)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{close_account, CloseAccount},
+};
+
+use crate::{error::EscrowError, instructions::basket::*, state::BasketEscrow};
+
+// Remaining accounts, offered legs first: [mint, token_program, vault, taker_ata]
+// then requested legs: [mint, token_program, taker_ata, maker_ata]
+#[derive(Accounts)]
+pub struct TakeBasket<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump,
+    )]
+    pub basket: Account<'info, BasketEscrow>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+//Pay every requested leg from taker to maker
+//Release every offered leg from its vault to taker
+//Close the vaults, the basket itself is closed by Anchor
+impl<'info> TakeBasket<'info> {
+    pub fn settle_legs(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let offered = self.basket.offered.clone();
+        let requested = self.basket.requested.clone();
+        require!(
+            remaining_accounts.len() == (offered.len() + requested.len()) * ACCOUNTS_PER_LEG,
+            EscrowError::BasketAccountsMismatch
+        );
+        let (offered_accounts, requested_accounts) =
+            remaining_accounts.split_at(offered.len() * ACCOUNTS_PER_LEG);
+
+        let taker = self.taker.to_account_info();
+        let maker = self.maker.to_account_info();
+        let basket = self.basket.to_account_info();
+        let cpi = LegCpi {
+            payer: &taker,
+            associated_token_program: &self.associated_token_program.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+        };
+
+        for (i, leg) in requested.iter().enumerate() {
+            let [mint, token_program, taker_ata, maker_ata] = leg_accounts(requested_accounts, i)? else {
+                return err!(EscrowError::BasketAccountsMismatch);
+            };
+            require_leg_mint(mint, leg, token_program)?;
+
+            require_ata(taker_ata, taker.key, &leg.mint, token_program.key)?;
+            cpi.create_ata(token_program, maker_ata, &maker, mint)?;
+            transfer_leg(token_program, taker_ata, maker_ata, &taker, mint, leg.amount, &[])?;
+        }
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump],
+        ]];
+
+        for (i, leg) in offered.iter().enumerate() {
+            let [mint, token_program, vault, taker_ata] = leg_accounts(offered_accounts, i)? else {
+                return err!(EscrowError::BasketAccountsMismatch);
+            };
+            require_leg_mint(mint, leg, token_program)?;
+
+            require_ata(vault, basket.key, &leg.mint, token_program.key)?;
+            cpi.create_ata(token_program, taker_ata, &taker, mint)?;
+            transfer_leg(token_program, vault, taker_ata, &basket, mint, vault_amount(vault)?, &signer_seeds)?;
+
+            let cpi_accounts = CloseAccount {
+                account: vault.clone(),
+                destination: maker.clone(),
+                authority: basket.clone(),
+            };
+
+            let cpi_context =
+                CpiContext::new_with_signer(token_program.clone(), cpi_accounts, &signer_seeds);
+
+            close_account(cpi_context)?;
+        }
+
+        Ok(())
+    }
+}
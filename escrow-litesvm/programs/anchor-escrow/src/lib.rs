@@ -3,11 +3,16 @@
 
 use anchor_lang::prelude::*;
 
-mod state;
+pub mod state;
 mod instructions;
+mod error;
+mod nft;
+pub mod vault_interface;
 mod tests;
 
 use instructions::*;
+use state::BasketLeg;
+use vault_interface::VaultDeposit;
 
 declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 
@@ -15,17 +20,91 @@ declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 pub mod anchor_escrow {
     use super::*;
 
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
+    // Remaining accounts, if any, are mint A's transfer hook accounts
+    pub fn make<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+    ) -> Result<()> {
         ctx.accounts.init_escrow(seed, receive, &ctx.bumps)?;
-        ctx.accounts.deposit(deposit)
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)
     }
 
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+    pub fn make_collection_offer<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        deposit: u64,
+    ) -> Result<()> {
+        ctx.accounts.init_collection_escrow(seed, &ctx.bumps)?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.refund_and_close_vault(ctx.remaining_accounts)
+    }
+
+    pub fn set_manager(ctx: Context<SetManager>, manager: Pubkey) -> Result<()> {
+        ctx.accounts.set_manager(manager)
+    }
+
+    pub fn amend(ctx: Context<Amend>, receive: u64) -> Result<()> {
+        ctx.accounts.amend_receive(receive)
+    }
+
+    // Anyone can bring an escrow made before collection offers and managers to the current layout
+    pub fn migrate_escrow(ctx: Context<MigrateEscrow>) -> Result<()> {
+        ctx.accounts.migrate()
+    }
+
+    // Remaining accounts, if any, are mint A's transfer hook accounts
+    pub fn take<'info>(ctx: Context<'_, '_, '_, 'info, Take<'info>>) -> Result<()> {
+        ctx.accounts.verify_mint_b()?;
+        ctx.accounts.deposit()?;
+        ctx.accounts.withdraw_and_close_vault(ctx.remaining_accounts, None)
+    }
+
+    // Remaining accounts: [vault program, deposit accounts..., mint A transfer hook accounts...]
+    pub fn take_into_vault<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        deposit_accounts: u8,
+    ) -> Result<()> {
+        let (vault_program, rest) = ctx
+            .remaining_accounts
+            .split_first()
+            .ok_or(error::EscrowError::VaultAccountsMismatch)?;
+        require!(
+            rest.len() >= deposit_accounts as usize,
+            error::EscrowError::VaultAccountsMismatch
+        );
+        let (accounts, hook_accounts) = rest.split_at(deposit_accounts as usize);
+
+        ctx.accounts.verify_mint_b()?;
         ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+        ctx.accounts.withdraw_and_close_vault(
+            hook_accounts,
+            Some(VaultDeposit {
+                program: vault_program,
+                accounts,
+            }),
+        )
+    }
+
+    pub fn make_basket<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeBasket<'info>>,
+        seed: u64,
+        offered: Vec<BasketLeg>,
+        requested: Vec<BasketLeg>,
+    ) -> Result<()> {
+        ctx.accounts.init_basket(seed, offered, requested, &ctx.bumps)?;
+        ctx.accounts.deposit_legs(ctx.remaining_accounts)
+    }
+
+    pub fn refund_basket<'info>(ctx: Context<'_, '_, '_, 'info, RefundBasket<'info>>) -> Result<()> {
+        ctx.accounts.refund_and_close_vaults(ctx.remaining_accounts)
+    }
+
+    pub fn take_basket<'info>(ctx: Context<'_, '_, '_, 'info, TakeBasket<'info>>) -> Result<()> {
+        ctx.accounts.settle_legs(ctx.remaining_accounts)
     }
 }
\ No newline at end of file
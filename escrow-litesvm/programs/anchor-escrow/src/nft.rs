@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    spl_token_2022::{
+        extension::{
+            group_member_pointer::GroupMemberPointer, group_pointer::GroupPointer,
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint as MintState,
+    },
+    Mint,
+};
+use spl_token_group_interface::state::{TokenGroup, TokenGroupMember};
+
+use crate::error::EscrowError;
+
+/// A mint is treated as an NFT when it has no decimals, exactly one token in circulation
+/// and no mint authority left to issue more
+pub fn is_nft(mint: &Mint) -> bool {
+    mint.decimals == 0 && mint.supply == 1 && mint.mint_authority.is_none()
+}
+
+/// Checks that the Token-2022 collection mint holds its own token group
+pub fn verify_collection(collection: &AccountInfo) -> Result<()> {
+    let data = collection.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    let pointer = state
+        .get_extension::<GroupPointer>()
+        .map_err(|_| EscrowError::NotACollection)?;
+    require!(
+        Option::<Pubkey>::from(pointer.group_address) == Some(collection.key()),
+        EscrowError::NotACollection
+    );
+
+    let group = state
+        .get_extension::<TokenGroup>()
+        .map_err(|_| EscrowError::NotACollection)?;
+    require_keys_eq!(group.mint, collection.key(), EscrowError::NotACollection);
+
+    Ok(())
+}
+
+/// Checks that `member` holds its own token group membership of `collection`.
+/// Token-2022 only initializes a member with the group's update authority signing,
+/// so membership can't be claimed without the collection authority
+pub fn verify_collection_member(member: &AccountInfo, collection: &AccountInfo) -> Result<()> {
+    let data = member.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    let pointer = state
+        .get_extension::<GroupMemberPointer>()
+        .map_err(|_| EscrowError::CollectionMismatch)?;
+    require!(
+        Option::<Pubkey>::from(pointer.member_address) == Some(member.key()),
+        EscrowError::CollectionMismatch
+    );
+
+    let membership = state
+        .get_extension::<TokenGroupMember>()
+        .map_err(|_| EscrowError::CollectionMismatch)?;
+    require_keys_eq!(membership.mint, member.key(), EscrowError::CollectionMismatch);
+    require_keys_eq!(membership.group, collection.key(), EscrowError::CollectionMismatch);
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+/// Upper bound on offered and requested legs, keeps `take_basket` within one transaction
+pub const MAX_BASKET_LEGS: usize = 4;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace, Debug, PartialEq)]
+pub struct BasketLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct BasketEscrow {
+    pub seed: u64,
+    pub maker: Pubkey,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub offered: Vec<BasketLeg>,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub requested: Vec<BasketLeg>,
+    pub bump: u8,
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::spl_pod::option::Nullable;
 
 #[account]
 #[derive(InitSpace, Debug)]
@@ -9,4 +10,26 @@ pub struct Escrow {
     pub mint_b: Pubkey,
     pub receive: u64,
     pub bump: u8,
-}
\ No newline at end of file
+    // Fields below were added after launch, migrate_escrow appends them to older escrows
+    pub collection: Pubkey,
+    pub manager: Pubkey,
+}
+
+impl Escrow {
+    /// Size of the escrows made before collection offers and managers, discriminator through `bump`
+    pub const LEGACY_LEN: usize = 8 + 8 + 32 * 3 + 8 + 1;
+
+    /// Collection offers accept any NFT of `collection` instead of a fixed `mint_b`
+    pub fn is_collection_offer(&self) -> bool {
+        !Pubkey::is_none(&self.collection)
+    }
+
+    /// The maker, or the manager when one is set, may refund and amend the offer
+    pub fn can_manage(&self, authority: &Pubkey) -> bool {
+        self.maker == *authority || (!Pubkey::is_none(&self.manager) && self.manager == *authority)
+    }
+
+    pub fn accepts_mint_b(&self, mint_b: &Pubkey) -> bool {
+        self.is_collection_offer() || self.mint_b == *mint_b
+    }
+}
@@ -1,3 +1,5 @@
+pub mod basket;
 pub mod escrow;
 
-pub use escrow::*;
\ No newline at end of file
+pub use basket::*;
+pub use escrow::*;
@@ -2,7 +2,7 @@
 mod tests {
 
     use {
-        crate::state::escrow,
+        crate::state::{escrow, BasketLeg},
         anchor_lang::{
             prelude::msg, 
             solana_program::program_pack::Pack, 
@@ -24,14 +24,17 @@ mod tests {
         }, 
         solana_rpc_client::rpc_client::RpcClient,
         solana_account::Account,
-        solana_instruction::Instruction, 
+        solana_instruction::{AccountMeta, Instruction},
         solana_keypair::Keypair, 
         solana_message::Message, 
         solana_native_token::LAMPORTS_PER_SOL, 
         solana_pubkey::Pubkey, 
         solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID, 
         solana_signer::Signer, 
+        solana_system_interface::instruction as system_instruction,
         solana_transaction::Transaction, 
+        spl_token_2022::extension::{ExtensionType, StateWithExtensions},
+        spl_token_metadata_interface::state::Field,
         solana_address::Address, 
         std::{
             path::PathBuf, 
@@ -318,6 +321,7 @@ mod tests {
                 taker: taker_key,
                 mint_a,
                 mint_b,
+                collection: None,
                 maker_ata_b,
                 taker_ata_a,
                 taker_ata_b,
@@ -325,6 +329,7 @@ mod tests {
                 vault,
                 associated_token_program,
                 token_program,
+                token_program_b: token_program,
                 system_program,
             }
             .to_account_metas(None),
@@ -437,6 +442,7 @@ mod tests {
         let refund_ix = Instruction {
             program_id: PROGRAM_ID,
             accounts: crate::accounts::Refund {
+                authority: maker_key,
                 maker: maker_key,
                 mint_a: mint_a,
                 maker_ata_a: maker_ata_a,
@@ -460,4 +466,1456 @@ mod tests {
         msg!("CUs Consumed: {}", tx.compute_units_consumed);
         msg!("Tx Signature: {}", tx.signature);
     }
+
+    // Load the escrow program into a fresh LiteSVM instance without touching devnet
+    fn setup_program() -> LiteSVM {
+        let mut program = LiteSVM::new();
+
+        let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../target/deploy/anchor_escrow.so");
+        let program_data = std::fs::read(so_path).expect("Failed to read program SO file");
+        program.add_program(PROGRAM_ID, &program_data);
+
+        program
+    }
+
+    fn funded_keypair(program: &mut LiteSVM) -> Keypair {
+        let keypair = Keypair::new();
+        program
+            .airdrop(&keypair.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL");
+        keypair
+    }
+
+    fn escrow_pda(maker: &Pubkey, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+            &PROGRAM_ID,
+        )
+        .0
+    }
+
+    // Creates a Token-2022 NFT mint whose metadata and group member pointers point at the mint itself
+    // The collection field is only written when a collection is given, it doesn't make the NFT a member
+    fn create_metadata_nft(
+        program: &mut LiteSVM,
+        payer: &Keypair,
+        update_authority: &Keypair,
+        collection: Option<Pubkey>,
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let token_program = spl_token_2022::ID;
+
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+                ExtensionType::MetadataPointer,
+                ExtensionType::GroupMemberPointer,
+            ])
+            .unwrap();
+        // Pre-fund the mint so Token-2022 can grow it when the metadata and membership are written
+        let lamports = program.minimum_balance_for_rent_exemption(space + 1024);
+
+        let mut instructions = vec![
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                lamports,
+                space as u64,
+                &token_program,
+            ),
+            spl_token_2022::extension::metadata_pointer::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                Some(update_authority.pubkey()),
+                Some(mint.pubkey()),
+            )
+            .unwrap(),
+            spl_token_2022::extension::group_member_pointer::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                Some(update_authority.pubkey()),
+                Some(mint.pubkey()),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint.pubkey(),
+                &update_authority.pubkey(),
+                None,
+                0,
+            )
+            .unwrap(),
+            spl_token_metadata_interface::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                &update_authority.pubkey(),
+                &mint.pubkey(),
+                &update_authority.pubkey(),
+                "Turbin3 NFT".to_string(),
+                "T3NFT".to_string(),
+                "https://example.com/nft.json".to_string(),
+            ),
+        ];
+
+        if let Some(collection) = collection {
+            instructions.push(spl_token_metadata_interface::instruction::update_field(
+                &token_program,
+                &mint.pubkey(),
+                &update_authority.pubkey(),
+                Field::Key("collection".to_string()),
+                collection.to_string(),
+            ));
+        }
+
+        let message = Message::new(&instructions, Some(&payer.pubkey()));
+        let transaction = Transaction::new(
+            &[payer, &mint, update_authority],
+            message,
+            program.latest_blockhash(),
+        );
+        program.send_transaction(transaction).unwrap();
+
+        mint.pubkey()
+    }
+
+    // Creates a Token-2022 collection mint holding its own metadata and token group
+    fn create_collection(program: &mut LiteSVM, authority: &Keypair) -> Pubkey {
+        let mint = Keypair::new();
+        let token_program = spl_token_2022::ID;
+
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+                ExtensionType::MetadataPointer,
+                ExtensionType::GroupPointer,
+            ])
+            .unwrap();
+        let lamports = program.minimum_balance_for_rent_exemption(space + 1024);
+
+        let instructions = [
+            system_instruction::create_account(
+                &authority.pubkey(),
+                &mint.pubkey(),
+                lamports,
+                space as u64,
+                &token_program,
+            ),
+            spl_token_2022::extension::metadata_pointer::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                Some(authority.pubkey()),
+                Some(mint.pubkey()),
+            )
+            .unwrap(),
+            spl_token_2022::extension::group_pointer::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                Some(authority.pubkey()),
+                Some(mint.pubkey()),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                None,
+                0,
+            )
+            .unwrap(),
+            spl_token_metadata_interface::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                &mint.pubkey(),
+                &authority.pubkey(),
+                "Turbin3 Collection".to_string(),
+                "T3C".to_string(),
+                "https://example.com/collection.json".to_string(),
+            ),
+            spl_token_group_interface::instruction::initialize_group(
+                &token_program,
+                &mint.pubkey(),
+                &mint.pubkey(),
+                &authority.pubkey(),
+                Some(authority.pubkey()),
+                100,
+            ),
+        ];
+
+        let message = Message::new(&instructions, Some(&authority.pubkey()));
+        let transaction = Transaction::new(&[authority, &mint], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        mint.pubkey()
+    }
+
+    // Adds `nft` to the collection's token group, both authorities have to sign
+    fn join_collection(
+        program: &mut LiteSVM,
+        nft: &Pubkey,
+        nft_authority: &Keypair,
+        collection: &Pubkey,
+        collection_authority: &Keypair,
+    ) {
+        let instruction = spl_token_group_interface::instruction::initialize_member(
+            &spl_token_2022::ID,
+            nft,
+            nft,
+            &nft_authority.pubkey(),
+            collection,
+            &collection_authority.pubkey(),
+        );
+
+        let message = Message::new(&[instruction], Some(&nft_authority.pubkey()));
+        let transaction = Transaction::new(
+            &[nft_authority, collection_authority],
+            message,
+            program.latest_blockhash(),
+        );
+        program.send_transaction(transaction).unwrap();
+    }
+
+    // Drops the mint authority so no more units can ever be minted
+    fn revoke_mint_authority(
+        program: &mut LiteSVM,
+        mint_authority: &Keypair,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) {
+        let instruction = spl_token_2022::instruction::set_authority(
+            token_program,
+            mint,
+            None,
+            spl_token_2022::instruction::AuthorityType::MintTokens,
+            &mint_authority.pubkey(),
+            &[],
+        )
+        .unwrap();
+
+        let message = Message::new(&[instruction], Some(&mint_authority.pubkey()));
+        let transaction = Transaction::new(&[mint_authority], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+    }
+
+    // Mints the single NFT unit into the owner's Token-2022 ATA and revokes the mint authority
+    fn mint_single_nft(
+        program: &mut LiteSVM,
+        mint_authority: &Keypair,
+        mint: &Pubkey,
+        owner: &Pubkey,
+    ) -> Pubkey {
+        let ata = CreateAssociatedTokenAccount::new(program, mint_authority, mint)
+            .owner(owner)
+            .token_program_id(&spl_token_2022::ID)
+            .send()
+            .unwrap();
+
+        MintTo::new(program, mint_authority, mint, &ata, 1)
+            .token_program_id(&spl_token_2022::ID)
+            .send()
+            .unwrap();
+        revoke_mint_authority(program, mint_authority, mint, &spl_token_2022::ID);
+
+        ata
+    }
+
+    #[test]
+    fn test_make_and_take_nft_for_tokens() {
+        let mut program = setup_program();
+        let maker = funded_keypair(&mut program);
+        let taker = funded_keypair(&mut program);
+
+        // Mint A is a classic single-supply NFT, Mint B a fungible token
+        let mint_a = CreateMint::new(&mut program, &maker)
+            .decimals(0)
+            .authority(&maker.pubkey())
+            .send()
+            .unwrap();
+        let mint_b = CreateMint::new(&mut program, &taker)
+            .decimals(6)
+            .authority(&taker.pubkey())
+            .send()
+            .unwrap();
+
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &maker, &mint_a)
+            .owner(&maker.pubkey()).send().unwrap();
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+            .owner(&taker.pubkey()).send().unwrap();
+        MintTo::new(&mut program, &maker, &mint_a, &maker_ata_a, 1).send().unwrap();
+        MintTo::new(&mut program, &taker, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+        let escrow = escrow_pda(&maker.pubkey(), 7);
+        let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed: 7,
+                deposit: 1,
+                receive: 500,
+            }
+            .data(),
+        };
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vault).unwrap().data).unwrap();
+        assert_eq!(vault_data.amount, 1);
+
+        let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker.pubkey(), &mint_b);
+
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b,
+                collection: None,
+                taker_ata_a,
+                taker_ata_b,
+                maker_ata_b,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                token_program_b: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take.data(),
+        };
+
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_nft =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_nft.amount, 1);
+
+        let maker_tokens =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_b).unwrap().data)
+                .unwrap();
+        assert_eq!(maker_tokens.amount, 500);
+    }
+
+    #[test]
+    fn test_take_across_token_programs() {
+        let mut program = setup_program();
+        let maker = funded_keypair(&mut program);
+        let taker = funded_keypair(&mut program);
+
+        // Token-2022 offered for SPL Token
+        let (mint_a, maker_ata_a) = create_funded_mint(&mut program, &maker, &spl_token_2022::ID);
+        let (mint_b, taker_ata_b) = create_funded_mint(&mut program, &taker, &TOKEN_PROGRAM_ID);
+        let ata_a = |owner: &Pubkey| {
+            associated_token::get_associated_token_address_with_program_id(owner, &mint_a, &spl_token_2022::ID)
+        };
+        let ata_b = |owner: &Pubkey| {
+            associated_token::get_associated_token_address_with_program_id(owner, &mint_b, &TOKEN_PROGRAM_ID)
+        };
+
+        let escrow = escrow_pda(&maker.pubkey(), 8);
+        let vault = ata_a(&escrow);
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token_2022::ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed: 8,
+                deposit: 300,
+                receive: 700,
+            }
+            .data(),
+        };
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let take_ix = |token_program_b: Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b,
+                collection: None,
+                taker_ata_a: ata_a(&taker.pubkey()),
+                taker_ata_b,
+                maker_ata_b: ata_b(&maker.pubkey()),
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token_2022::ID,
+                token_program_b,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take.data(),
+        };
+
+        // Mint B has to come with its own token program
+        let message = Message::new(&[take_ix(spl_token_2022::ID)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        let message = Message::new(&[take_ix(TOKEN_PROGRAM_ID)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_balance(&program, &ata_a(&taker.pubkey())), 300);
+        assert_eq!(token_balance(&program, &ata_b(&maker.pubkey())), 700);
+        assert!(program.get_account(&vault).is_none_or(|account| account.data.is_empty()));
+    }
+
+    #[test]
+    fn test_migrate_legacy_escrow() {
+        use anchor_lang::{Discriminator, Space};
+
+        let mut program = setup_program();
+        let maker = funded_keypair(&mut program);
+        let taker = funded_keypair(&mut program);
+        let (mint_a, maker_ata_a) = create_funded_mint(&mut program, &maker, &TOKEN_PROGRAM_ID);
+        let (mint_b, taker_ata_b) = create_funded_mint(&mut program, &taker, &TOKEN_PROGRAM_ID);
+
+        let escrow = escrow_pda(&maker.pubkey(), 9);
+        let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed: 9,
+                deposit: 250,
+                receive: 400,
+            }
+            .data(),
+        };
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // Cut the escrow back to what the program wrote before collection offers and managers
+        let mut legacy = program.get_account(&escrow).unwrap();
+        legacy.data.truncate(crate::state::Escrow::LEGACY_LEN);
+        legacy.lamports = program.minimum_balance_for_rent_exemption(legacy.data.len());
+        program.set_account(escrow, legacy).unwrap();
+        assert_eq!(program.get_account(&escrow).unwrap().data.len(), 121);
+
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b,
+                collection: None,
+                taker_ata_a: associated_token::get_associated_token_address(&taker.pubkey(), &mint_a),
+                taker_ata_b,
+                maker_ata_b: associated_token::get_associated_token_address(&maker.pubkey(), &mint_b),
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                token_program_b: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take.data(),
+        };
+        let migrate_ix = |account: Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::MigrateEscrow {
+                payer: taker.pubkey(),
+                escrow: account,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::MigrateEscrow {}.data(),
+        };
+
+        // The short account doesn't load until it's migrated
+        let message = Message::new(std::slice::from_ref(&take_ix), Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        // Only legacy escrows are grown, the vault isn't one
+        let message = Message::new(&[migrate_ix(vault)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        let message = Message::new(&[migrate_ix(escrow)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let account = program.get_account(&escrow).unwrap();
+        assert!(account.data.starts_with(crate::state::Escrow::DISCRIMINATOR));
+        assert_eq!(account.data.len(), 8 + crate::state::Escrow::INIT_SPACE);
+        assert_eq!(account.lamports, program.minimum_balance_for_rent_exemption(account.data.len()));
+        let migrated = crate::state::Escrow::try_deserialize(&mut account.data.as_ref()).unwrap();
+        assert_eq!((migrated.seed, migrated.maker, migrated.mint_b), (9, maker.pubkey(), mint_b));
+        assert_eq!(migrated.receive, 400);
+        assert!(!migrated.is_collection_offer());
+        assert!(!migrated.can_manage(&Pubkey::default()));
+
+        // Already migrated
+        program.expire_blockhash();
+        let message = Message::new(&[migrate_ix(escrow)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(
+            token_balance(&program, &associated_token::get_associated_token_address(&maker.pubkey(), &mint_b)),
+            400
+        );
+    }
+
+    #[test]
+    fn test_make_rejects_multiple_units_of_requested_nft() {
+        let mut program = setup_program();
+        let maker = funded_keypair(&mut program);
+
+        let mint_a = CreateMint::new(&mut program, &maker)
+            .decimals(6)
+            .authority(&maker.pubkey())
+            .send()
+            .unwrap();
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &maker, &mint_a)
+            .owner(&maker.pubkey()).send().unwrap();
+        MintTo::new(&mut program, &maker, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+
+        // Mint B is an NFT held by someone else, asking for 5 of it can never settle
+        let mint_b = CreateMint::new(&mut program, &maker)
+            .decimals(0)
+            .authority(&maker.pubkey())
+            .send()
+            .unwrap();
+        let holder_ata_b = CreateAssociatedTokenAccount::new(&mut program, &maker, &mint_b)
+            .owner(&Keypair::new().pubkey()).send().unwrap();
+        MintTo::new(&mut program, &maker, &mint_b, &holder_ata_b, 1).send().unwrap();
+        revoke_mint_authority(&mut program, &maker, &mint_b, &TOKEN_PROGRAM_ID);
+
+        let escrow = escrow_pda(&maker.pubkey(), 8);
+        let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed: 8,
+                deposit: 10,
+                receive: 5,
+            }
+            .data(),
+        };
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+    }
+
+    // Maker offers fungible Token-2022 tokens for any NFT of a collection
+    // Returns the maker, collection mint, mint A, escrow and vault of the open offer
+    fn setup_collection_offer(
+        program: &mut LiteSVM,
+        collection_authority: &Keypair,
+    ) -> (Keypair, Pubkey, Pubkey, Pubkey, Pubkey) {
+        let maker = funded_keypair(program);
+        let token_program = spl_token_2022::ID;
+
+        let collection = create_collection(program, collection_authority);
+
+        let mint_a = CreateMint::new(program, &maker)
+            .decimals(6)
+            .authority(&maker.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let maker_ata_a = CreateAssociatedTokenAccount::new(program, &maker, &mint_a)
+            .owner(&maker.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(program, &maker, &mint_a, &maker_ata_a, 1000000000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let escrow = escrow_pda(&maker.pubkey(), 9);
+        let vault = associated_token::get_associated_token_address_with_program_id(
+            &escrow,
+            &mint_a,
+            &token_program,
+        );
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker: maker.pubkey(),
+                mint_a,
+                mint_b: collection,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::MakeCollectionOffer {
+                seed: 9,
+                deposit: 250,
+            }
+            .data(),
+        };
+
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.collection, collection);
+        assert_eq!(escrow_data.mint_b, Pubkey::default());
+        assert_eq!(escrow_data.receive, 1);
+
+        (maker, collection, mint_a, escrow, vault)
+    }
+
+    fn take_collection_offer_ix(
+        taker: &Pubkey,
+        maker: &Pubkey,
+        mint_a: Pubkey,
+        nft: Pubkey,
+        collection: Pubkey,
+        escrow: Pubkey,
+        vault: Pubkey,
+    ) -> Instruction {
+        let token_program = spl_token_2022::ID;
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: *taker,
+                maker: *maker,
+                mint_a,
+                mint_b: nft,
+                collection: Some(collection),
+                taker_ata_a: associated_token::get_associated_token_address_with_program_id(
+                    taker,
+                    &mint_a,
+                    &token_program,
+                ),
+                taker_ata_b: associated_token::get_associated_token_address_with_program_id(
+                    taker,
+                    &nft,
+                    &token_program,
+                ),
+                maker_ata_b: associated_token::get_associated_token_address_with_program_id(
+                    maker,
+                    &nft,
+                    &token_program,
+                ),
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                token_program_b: token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take.data(),
+        }
+    }
+
+    #[test]
+    fn test_take_collection_offer_with_member_nft() {
+        let mut program = setup_program();
+        let collection_authority = funded_keypair(&mut program);
+        let (maker, collection, mint_a, escrow, vault) =
+            setup_collection_offer(&mut program, &collection_authority);
+
+        let taker = funded_keypair(&mut program);
+        let nft = create_metadata_nft(&mut program, &collection_authority, &collection_authority, None);
+        join_collection(&mut program, &nft, &collection_authority, &collection, &collection_authority);
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &collection_authority, &nft)
+            .owner(&taker.pubkey())
+            .token_program_id(&spl_token_2022::ID)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &collection_authority, &nft, &taker_ata_b, 1)
+            .token_program_id(&spl_token_2022::ID)
+            .send()
+            .unwrap();
+
+        let take_ix = take_collection_offer_ix(
+            &taker.pubkey(),
+            &maker.pubkey(),
+            mint_a,
+            nft,
+            collection,
+            escrow,
+            vault,
+        );
+
+        // A member whose mint authority can still issue more units isn't an NFT yet
+        let message = Message::new(std::slice::from_ref(&take_ix), Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        revoke_mint_authority(&mut program, &collection_authority, &nft, &spl_token_2022::ID);
+        program.expire_blockhash();
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let maker_ata_b = associated_token::get_associated_token_address_with_program_id(
+            &maker.pubkey(),
+            &nft,
+            &spl_token_2022::ID,
+        );
+        let maker_nft_account = program.get_account(&maker_ata_b).unwrap();
+        let maker_nft =
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&maker_nft_account.data)
+                .unwrap();
+        assert_eq!(maker_nft.base.amount, 1);
+
+        let taker_ata_a = associated_token::get_associated_token_address_with_program_id(
+            &taker.pubkey(),
+            &mint_a,
+            &spl_token_2022::ID,
+        );
+        let taker_tokens_account = program.get_account(&taker_ata_a).unwrap();
+        let taker_tokens = StateWithExtensions::<spl_token_2022::state::Account>::unpack(
+            &taker_tokens_account.data,
+        )
+        .unwrap();
+        assert_eq!(taker_tokens.base.amount, 250);
+    }
+
+    #[test]
+    fn test_take_collection_offer_rejects_unverified_nft() {
+        let mut program = setup_program();
+        let collection_authority = funded_keypair(&mut program);
+        let (maker, collection, mint_a, escrow, vault) =
+            setup_collection_offer(&mut program, &collection_authority);
+
+        // The taker forges an NFT that claims the collection without its authority signing off
+        let taker = funded_keypair(&mut program);
+        let forged = create_metadata_nft(&mut program, &taker, &taker, Some(collection));
+        mint_single_nft(&mut program, &taker, &forged, &taker.pubkey());
+
+        let take_ix = take_collection_offer_ix(
+            &taker.pubkey(),
+            &maker.pubkey(),
+            mint_a,
+            forged,
+            collection,
+            escrow,
+            vault,
+        );
+
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        // The offer is still open
+        let vault_account = program.get_account(&vault).unwrap();
+        let vault_data =
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&vault_account.data)
+                .unwrap();
+        assert_eq!(vault_data.base.amount, 250);
+    }
+
+    #[test]
+    fn test_take_collection_offer_rejects_authority_handoff() {
+        let mut program = setup_program();
+        let collection_authority = funded_keypair(&mut program);
+        let (maker, collection, mint_a, escrow, vault) =
+            setup_collection_offer(&mut program, &collection_authority);
+
+        // The forger names the collection and hands the metadata to its authority, who never signs
+        let taker = funded_keypair(&mut program);
+        let forged = create_metadata_nft(&mut program, &taker, &taker, Some(collection));
+        let handoff_ix = spl_token_metadata_interface::instruction::update_authority(
+            &spl_token_2022::ID,
+            &forged,
+            &taker.pubkey(),
+            Some(collection_authority.pubkey()).try_into().unwrap(),
+        );
+        let message = Message::new(&[handoff_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        mint_single_nft(&mut program, &taker, &forged, &taker.pubkey());
+
+        let take_ix = take_collection_offer_ix(
+            &taker.pubkey(),
+            &maker.pubkey(),
+            mint_a,
+            forged,
+            collection,
+            escrow,
+            vault,
+        );
+
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        let vault_account = program.get_account(&vault).unwrap();
+        let vault_data =
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&vault_account.data)
+                .unwrap();
+        assert_eq!(vault_data.base.amount, 250);
+    }
+
+    // Creates a 6 decimal mint under `token_program` owned by `authority` and funds the owner's ATA with it
+    fn create_funded_mint(
+        program: &mut LiteSVM,
+        authority: &Keypair,
+        token_program: &Pubkey,
+    ) -> (Pubkey, Pubkey) {
+        let mint = CreateMint::new(program, authority)
+            .decimals(6)
+            .authority(&authority.pubkey())
+            .token_program_id(token_program)
+            .send()
+            .unwrap();
+        let ata = CreateAssociatedTokenAccount::new(program, authority, &mint)
+            .owner(&authority.pubkey())
+            .token_program_id(token_program)
+            .send()
+            .unwrap();
+        MintTo::new(program, authority, &mint, &ata, 1000000000)
+            .token_program_id(token_program)
+            .send()
+            .unwrap();
+
+        (mint, ata)
+    }
+
+    // Works for SPL Token and Token-2022 accounts alike
+    fn token_balance(program: &LiteSVM, account: &Pubkey) -> u64 {
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(
+            &program.get_account(account).unwrap().data,
+        )
+        .unwrap()
+        .base
+        .amount
+    }
+
+    // A basket mint together with the token program that owns it
+    type LegMint = (Pubkey, Pubkey);
+
+    fn leg_ata(owner: &Pubkey, (mint, token_program): &LegMint) -> Pubkey {
+        associated_token::get_associated_token_address_with_program_id(owner, mint, token_program)
+    }
+
+    fn leg_metas(legs: &[(LegMint, Pubkey, Pubkey)]) -> Vec<AccountMeta> {
+        legs.iter()
+            .flat_map(|((mint, token_program), from, to)| {
+                [
+                    AccountMeta::new_readonly(*mint, false),
+                    AccountMeta::new_readonly(*token_program, false),
+                    AccountMeta::new(*from, false),
+                    AccountMeta::new(*to, false),
+                ]
+            })
+            .collect()
+    }
+
+    fn basket_pda(maker: &Pubkey, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"basket", maker.as_ref(), &seed.to_le_bytes()], &PROGRAM_ID).0
+    }
+
+    // Offered legs deposit from the maker's ATAs, requested legs only pass their mint
+    fn make_basket_ix(
+        maker: &Keypair,
+        seed: u64,
+        offered: &[(LegMint, u64)],
+        requested: &[(LegMint, u64)],
+    ) -> Instruction {
+        let basket = basket_pda(&maker.pubkey(), seed);
+        let mut accounts = crate::accounts::MakeBasket {
+            maker: maker.pubkey(),
+            basket,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(leg_metas(
+            &offered
+                .iter()
+                .map(|(leg, _)| (*leg, leg_ata(&maker.pubkey(), leg), leg_ata(&basket, leg)))
+                .collect::<Vec<_>>(),
+        ));
+        accounts.extend(
+            requested
+                .iter()
+                .map(|((mint, _), _)| AccountMeta::new_readonly(*mint, false)),
+        );
+
+        let legs = |legs: &[(LegMint, u64)]| {
+            legs.iter()
+                .map(|((mint, _), amount)| BasketLeg { mint: *mint, amount: *amount })
+                .collect()
+        };
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: crate::instruction::MakeBasket {
+                seed,
+                offered: legs(offered),
+                requested: legs(requested),
+            }
+            .data(),
+        }
+    }
+
+    // Maker offers two mints and asks for two mints in return, one of each pair is Token-2022
+    // Returns the maker, taker, offered mints, requested mints and the basket PDA
+    fn setup_basket() -> (LiteSVM, Keypair, Keypair, [LegMint; 2], [LegMint; 2], Pubkey) {
+        let mut program = setup_program();
+        let maker = funded_keypair(&mut program);
+        let taker = funded_keypair(&mut program);
+
+        let token_2022 = spl_token_2022::ID;
+        let (offered_1, _) = create_funded_mint(&mut program, &maker, &TOKEN_PROGRAM_ID);
+        let (offered_2, _) = create_funded_mint(&mut program, &maker, &token_2022);
+        let (requested_1, _) = create_funded_mint(&mut program, &taker, &TOKEN_PROGRAM_ID);
+        let (requested_2, _) = create_funded_mint(&mut program, &taker, &token_2022);
+        let offered = [(offered_1, TOKEN_PROGRAM_ID), (offered_2, token_2022)];
+        let requested = [(requested_1, TOKEN_PROGRAM_ID), (requested_2, token_2022)];
+
+        let basket = basket_pda(&maker.pubkey(), 42);
+        let vault_1 = leg_ata(&basket, &offered[0]);
+        let vault_2 = leg_ata(&basket, &offered[1]);
+
+        let make_basket_ix = make_basket_ix(
+            &maker,
+            42,
+            &[(offered[0], 10), (offered[1], 20)],
+            &[(requested[0], 30), (requested[1], 40)],
+        );
+
+        let message = Message::new(&[make_basket_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_balance(&program, &vault_1), 10);
+        assert_eq!(token_balance(&program, &vault_2), 20);
+
+        let basket_data = crate::state::BasketEscrow::try_deserialize(
+            &mut program.get_account(&basket).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(basket_data.maker, maker.pubkey());
+        assert_eq!(basket_data.offered.len(), 2);
+        assert_eq!(basket_data.requested.len(), 2);
+
+        (program, maker, taker, offered, requested, basket)
+    }
+
+    fn take_basket_ix(
+        maker: &Keypair,
+        taker: &Keypair,
+        offered: &[LegMint],
+        requested: &[LegMint],
+        basket: Pubkey,
+    ) -> Instruction {
+        let mut accounts = crate::accounts::TakeBasket {
+            taker: taker.pubkey(),
+            maker: maker.pubkey(),
+            basket,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        // Offered legs first, then requested legs
+        let offered_legs = offered
+            .iter()
+            .map(|leg| (*leg, leg_ata(&basket, leg), leg_ata(&taker.pubkey(), leg)));
+        let requested_legs = requested
+            .iter()
+            .map(|leg| (*leg, leg_ata(&taker.pubkey(), leg), leg_ata(&maker.pubkey(), leg)));
+        accounts.extend(leg_metas(&offered_legs.chain(requested_legs).collect::<Vec<_>>()));
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: crate::instruction::TakeBasket.data(),
+        }
+    }
+
+    fn refund_basket_ix(maker: &Keypair, offered: &[LegMint], basket: Pubkey) -> Instruction {
+        let mut accounts = crate::accounts::RefundBasket {
+            maker: maker.pubkey(),
+            basket,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(leg_metas(
+            &offered
+                .iter()
+                .map(|leg| (*leg, leg_ata(&basket, leg), leg_ata(&maker.pubkey(), leg)))
+                .collect::<Vec<_>>(),
+        ));
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: crate::instruction::RefundBasket.data(),
+        }
+    }
+
+    // Sends `amount` of an offered mint from the maker's ATA straight into the basket vault
+    fn top_up_vault(program: &mut LiteSVM, maker: &Keypair, leg: &LegMint, basket: &Pubkey, amount: u64) {
+        let (mint, token_program) = leg;
+        let transfer_ix = spl_token_2022::instruction::transfer_checked(
+            token_program,
+            &leg_ata(&maker.pubkey(), leg),
+            mint,
+            &leg_ata(basket, leg),
+            &maker.pubkey(),
+            &[],
+            amount,
+            6,
+        )
+        .unwrap();
+        let message = Message::new(&[transfer_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+    }
+
+    #[test]
+    fn test_make_and_take_basket() {
+        let (mut program, maker, taker, offered, requested, basket) = setup_basket();
+
+        let take_basket_ix = take_basket_ix(&maker, &taker, &offered, &requested, basket);
+        let message = Message::new(&[take_basket_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        let tx = program.send_transaction(transaction).unwrap();
+        msg!("Take basket CUs Consumed: {}", tx.compute_units_consumed);
+
+        assert_eq!(token_balance(&program, &leg_ata(&taker.pubkey(), &offered[0])), 10);
+        assert_eq!(token_balance(&program, &leg_ata(&taker.pubkey(), &offered[1])), 20);
+        assert_eq!(token_balance(&program, &leg_ata(&maker.pubkey(), &requested[0])), 30);
+        assert_eq!(token_balance(&program, &leg_ata(&maker.pubkey(), &requested[1])), 40);
+
+        // Vaults and basket are closed
+        for account in [leg_ata(&basket, &offered[0]), leg_ata(&basket, &offered[1]), basket] {
+            assert!(program.get_account(&account).is_none_or(|a| a.lamports == 0));
+        }
+    }
+
+    #[test]
+    fn test_take_full_basket_fits_one_transaction() {
+        let mut program = setup_program();
+        let maker = funded_keypair(&mut program);
+        let taker = funded_keypair(&mut program);
+
+        // MAX_BASKET_LEGS on both sides, alternating token programs
+        let mut legs = |owner: &Keypair| -> Vec<LegMint> {
+            (0..crate::state::MAX_BASKET_LEGS)
+                .map(|i| {
+                    let token_program = if i % 2 == 0 { TOKEN_PROGRAM_ID } else { spl_token_2022::ID };
+                    (create_funded_mint(&mut program, owner, &token_program).0, token_program)
+                })
+                .collect()
+        };
+        let offered = legs(&maker);
+        let requested = legs(&taker);
+        let with_amounts = |legs: &[LegMint]| legs.iter().map(|leg| (*leg, 15)).collect::<Vec<_>>();
+
+        let make_ix = make_basket_ix(&maker, 44, &with_amounts(&offered), &with_amounts(&requested));
+        let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // LiteSVM doesn't enforce the packet size, a validator refuses anything over 1232 bytes
+        let basket = basket_pda(&maker.pubkey(), 44);
+        let take_ix = take_basket_ix(&maker, &taker, &offered, &requested, basket);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        let size = bincode::serialize(&transaction).unwrap().len();
+        msg!("Full take basket transaction size: {}", size);
+        assert!(size <= 1232);
+
+        program.send_transaction(transaction).unwrap();
+        for leg in &offered {
+            assert_eq!(token_balance(&program, &leg_ata(&taker.pubkey(), leg)), 15);
+        }
+        for leg in &requested {
+            assert_eq!(token_balance(&program, &leg_ata(&maker.pubkey(), leg)), 15);
+        }
+    }
+
+    // A Token-2022 mint with the transfer hook extension, `authority` holds a supply of it
+    fn create_hook_mint(program: &mut LiteSVM, authority: &Keypair) -> LegMint {
+        let mint = Keypair::new();
+        let token_program = spl_token_2022::ID;
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferHook,
+        ])
+        .unwrap();
+
+        let instructions = [
+            system_instruction::create_account(
+                &authority.pubkey(),
+                &mint.pubkey(),
+                program.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &token_program,
+            ),
+            // No hook program yet, the authority could set one at any time
+            spl_token_2022::extension::transfer_hook::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                Some(authority.pubkey()),
+                None,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        ];
+        let message = Message::new(&instructions, Some(&authority.pubkey()));
+        let transaction = Transaction::new(&[authority, &mint], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let ata = CreateAssociatedTokenAccount::new(program, authority, &mint.pubkey())
+            .owner(&authority.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(program, authority, &mint.pubkey(), &ata, 1000000000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        (mint.pubkey(), token_program)
+    }
+
+    #[test]
+    fn test_make_basket_rejects_transfer_hook_mints() {
+        let mut program = setup_program();
+        let maker = funded_keypair(&mut program);
+        let plain_offered = (create_funded_mint(&mut program, &maker, &spl_token_2022::ID).0, spl_token_2022::ID);
+        let hook_offered = create_hook_mint(&mut program, &maker);
+        let plain_requested = (create_funded_mint(&mut program, &maker, &TOKEN_PROGRAM_ID).0, TOKEN_PROGRAM_ID);
+        let hook_requested = create_hook_mint(&mut program, &maker);
+
+        let mut try_make = |offered: LegMint, requested: LegMint| {
+            let make_ix = make_basket_ix(&maker, 45, &[(offered, 10)], &[(requested, 10)]);
+            let message = Message::new(&[make_ix], Some(&maker.pubkey()));
+            let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+            program.send_transaction(transaction).is_ok()
+        };
+
+        // Neither side may use one, the requested side is checked at make too
+        assert!(!try_make(hook_offered, plain_requested));
+        assert!(!try_make(plain_offered, hook_requested));
+        assert!(try_make(plain_offered, plain_requested));
+    }
+
+    #[test]
+    fn test_take_basket_rejects_missing_leg() {
+        let (mut program, maker, taker, offered, requested, basket) = setup_basket();
+
+        // The second requested leg is left out, nothing may settle
+        let take_basket_ix = take_basket_ix(&maker, &taker, &offered, &requested[..1], basket);
+        let message = Message::new(&[take_basket_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+        assert_eq!(token_balance(&program, &leg_ata(&basket, &offered[0])), 10);
+    }
+
+    #[test]
+    fn test_refund_basket() {
+        let (mut program, maker, _taker, offered, _requested, basket) = setup_basket();
+
+        let refund_basket_ix = refund_basket_ix(&maker, &offered, basket);
+        let message = Message::new(&[refund_basket_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_balance(&program, &leg_ata(&maker.pubkey(), &offered[0])), 1000000000);
+        assert_eq!(token_balance(&program, &leg_ata(&maker.pubkey(), &offered[1])), 1000000000);
+    }
+
+    #[test]
+    fn test_basket_vault_top_up_does_not_block_settlement() {
+        let (mut program, maker, taker, offered, requested, basket) = setup_basket();
+
+        // Extra tokens sent to a vault ride along instead of blocking its close
+        top_up_vault(&mut program, &maker, &offered[1], &basket, 5);
+        let take_basket_ix = take_basket_ix(&maker, &taker, &offered, &requested, basket);
+        let message = Message::new(&[take_basket_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(token_balance(&program, &leg_ata(&taker.pubkey(), &offered[1])), 25);
+        assert!(program
+            .get_account(&leg_ata(&basket, &offered[1]))
+            .is_none_or(|a| a.lamports == 0));
+
+        // Same for a refund
+        let (mut program, maker, _taker, offered, _requested, basket) = setup_basket();
+        top_up_vault(&mut program, &maker, &offered[0], &basket, 5);
+        let refund_basket_ix = refund_basket_ix(&maker, &offered, basket);
+        let message = Message::new(&[refund_basket_ix], Some(&maker.pubkey()));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(token_balance(&program, &leg_ata(&maker.pubkey(), &offered[0])), 1000000000);
+    }
+
+    #[test]
+    fn test_manager_amends_and_refunds_for_maker() {
+        let (mut program, maker, _, maker_key, _, mint_a, mint_b, maker_ata_a, _, _, _, escrow) =
+            setup();
+        let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+        let manager = funded_keypair(&mut program);
+        let stranger = funded_keypair(&mut program);
+
+        MintTo::new(&mut program, &maker, &mint_a, &maker_ata_a, 1000000000)
+            .send()
+            .unwrap();
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker: maker_key,
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                deposit: 10,
+                seed: 123u64,
+                receive: 10,
+            }
+            .data(),
+        };
+
+        let set_manager_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetManager {
+                maker: maker_key,
+                escrow,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetManager {
+                manager: manager.pubkey(),
+            }
+            .data(),
+        };
+
+        let message = Message::new(&[make_ix, set_manager_ix], Some(&maker_key));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let amend_ix = |authority: &Pubkey, receive: u64| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Amend {
+                authority: *authority,
+                mint_b,
+                escrow,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Amend { receive }.data(),
+        };
+
+        // Someone who is neither maker nor manager cannot touch the offer
+        let message = Message::new(&[amend_ix(&stranger.pubkey(), 1)], Some(&stranger.pubkey()));
+        let transaction = Transaction::new(&[&stranger], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        let message = Message::new(&[amend_ix(&manager.pubkey(), 25)], Some(&manager.pubkey()));
+        let transaction = Transaction::new(&[&manager], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.manager, manager.pubkey());
+        assert_eq!(escrow_data.receive, 25);
+
+        // The manager refunds, the deposit and the rent still go back to the maker
+        let maker_lamports_before = program.get_account(&maker_key).unwrap().lamports;
+
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                authority: manager.pubkey(),
+                maker: maker_key,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                vault,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund.data(),
+        };
+
+        let message = Message::new(&[refund_ix], Some(&manager.pubkey()));
+        let transaction = Transaction::new(&[&manager], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_balance(&program, &maker_ata_a), 1000000000);
+        assert!(program.get_account(&maker_key).unwrap().lamports > maker_lamports_before);
+    }
+
+    #[test]
+    fn test_take_into_unsupported_vault_fails() {
+        let (
+            mut program,
+            maker,
+            taker,
+            maker_key,
+            taker_key,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            maker_ata_b,
+            taker_ata_a,
+            taker_ata_b,
+            escrow,
+        ) = setup();
+        let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+        MintTo::new(&mut program, &maker, &mint_a, &maker_ata_a, 1000000000)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &taker, &mint_b, &taker_ata_b, 1000000000)
+            .send()
+            .unwrap();
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker: maker_key,
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                deposit: 10,
+                seed: 123u64,
+                receive: 10,
+            }
+            .data(),
+        };
+
+        let message = Message::new(&[make_ix], Some(&maker_key));
+        let transaction = Transaction::new(&[&maker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // Only known vault programs may receive mint A, anything else aborts the whole take
+        let mut accounts = crate::accounts::Take {
+            maker: maker_key,
+            taker: taker_key,
+            mint_a,
+            mint_b,
+            collection: None,
+            maker_ata_b,
+            taker_ata_a,
+            taker_ata_b,
+            escrow,
+            vault,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: TOKEN_PROGRAM_ID,
+            token_program_b: TOKEN_PROGRAM_ID,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+        accounts.push(AccountMeta::new_readonly(taker_key, true));
+        accounts.push(AccountMeta::new(taker_ata_a, false));
+
+        let take_into_vault_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: crate::instruction::TakeIntoVault { deposit_accounts: 2 }.data(),
+        };
+
+        let message = Message::new(&[take_into_vault_ix], Some(&taker_key));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        assert_eq!(token_balance(&program, &vault), 10);
+        assert_eq!(token_balance(&program, &taker_ata_a), 0);
+    }
 }
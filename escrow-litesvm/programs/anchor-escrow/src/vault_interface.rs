@@ -0,0 +1,115 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, program::invoke},
+};
+
+use crate::error::EscrowError;
+
+/// A vault program the taker can have mint A deposited into during `take_into_vault`
+///
+/// Adapters only describe how to call the vault's deposit, the accounts themselves
+/// are forwarded untouched from the take's remaining accounts
+pub trait VaultProgram {
+    fn program_id(&self) -> Pubkey;
+
+    /// Makes sure the forwarded accounts deposit from `depositor_ata` on behalf of `depositor`
+    fn check_depositor(
+        &self,
+        accounts: &[AccountInfo],
+        depositor: &Pubkey,
+        depositor_ata: &Pubkey,
+    ) -> Result<()>;
+
+    fn deposit_data(&self, amount: u64) -> Vec<u8>;
+}
+
+/// Every vault `take_into_vault` can deposit into, a new adapter only needs adding here
+pub const VAULT_PROGRAMS: &[&dyn VaultProgram] = &[&Week1Vault];
+
+/// `week1_challenge` vault, `deposit(amount)` pulls from `user_ata` signed by `sender`
+///
+/// The program id and discriminator are copied from week1_challenge instead of coming from
+/// its `cpi` crate, the escrow doesn't build against it. week1_challenge's tests check both
+/// against the real `deposit`, update them together.
+pub struct Week1Vault;
+
+impl Week1Vault {
+    pub const PROGRAM_ID: Pubkey = pubkey!("AkTTSsoAmjbmDQTVzFmoEWJ5o2j78xGBtSVuB68irJiJ");
+}
+
+impl VaultProgram for Week1Vault {
+    fn program_id(&self) -> Pubkey {
+        Self::PROGRAM_ID
+    }
+
+    fn check_depositor(
+        &self,
+        accounts: &[AccountInfo],
+        depositor: &Pubkey,
+        depositor_ata: &Pubkey,
+    ) -> Result<()> {
+        // `sender` is the first account of `DepositWithdraw`
+        let sender = accounts.first().ok_or(EscrowError::VaultAccountsMismatch)?;
+        require_keys_eq!(sender.key(), *depositor, EscrowError::VaultAccountsMismatch);
+        require!(
+            accounts.iter().any(|account| account.key() == *depositor_ata),
+            EscrowError::VaultAccountsMismatch
+        );
+        Ok(())
+    }
+
+    fn deposit_data(&self, amount: u64) -> Vec<u8> {
+        // sha256("global:deposit")[..8]
+        let mut data = vec![242, 35, 198, 137, 82, 225, 242, 182];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data
+    }
+}
+
+/// Accounts forwarded by the taker for the vault deposit CPI
+pub struct VaultDeposit<'a, 'info> {
+    pub program: &'a AccountInfo<'info>,
+    pub accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> VaultDeposit<'a, 'info> {
+    pub fn deposit(&self, depositor: &Pubkey, depositor_ata: &Pubkey, amount: u64) -> Result<()> {
+        let vault_program = VAULT_PROGRAMS
+            .iter()
+            .find(|vault_program| vault_program.program_id() == self.program.key())
+            .ok_or(EscrowError::UnsupportedVault)?;
+
+        self.invoke(*vault_program, depositor, depositor_ata, amount)
+    }
+
+    fn invoke(
+        &self,
+        vault_program: &dyn VaultProgram,
+        depositor: &Pubkey,
+        depositor_ata: &Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        vault_program.check_depositor(self.accounts, depositor, depositor_ata)?;
+
+        let deposit_ix = Instruction {
+            program_id: vault_program.program_id(),
+            accounts: self
+                .accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.key(),
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: vault_program.deposit_data(amount),
+        };
+
+        let mut account_infos = self.accounts.to_vec();
+        account_infos.push(self.program.clone());
+
+        invoke(&deposit_ix, &account_infos)?;
+
+        Ok(())
+    }
+}
@@ -4,4 +4,14 @@ use anchor_lang::prelude::*;
 pub enum ErrorCode {
     #[msg("Custom error message")]
     CustomError,
+    #[msg("TransferHook: Owner is not whitelisted")]
+    OwnerNotWhitelisted,
+    #[msg("TransferHook: Whitelist belongs to another admin")]
+    WhitelistAdminMismatch,
+    #[msg("TransferHook: Transfers are paused")]
+    TransfersPaused,
+    #[msg("TransferHook: Owner's whitelist approval has expired")]
+    WhitelistExpired,
+    #[msg("TransferHook: Signer is not the mint's transfer hook authority")]
+    NotHookAuthority,
 }
@@ -26,12 +26,72 @@ pub struct InitializeExtraAccountMetaList<'info> {
 
 impl<'info> InitializeExtraAccountMetaList<'info> {
     pub fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
-        let (whitelist_pubkey, _bump) =
-            Pubkey::find_program_address(&[b"whitelist"], &week1_challenge::ID);
-        Ok(vec![ExtraAccountMeta::new_with_pubkey(
-            &whitelist_pubkey,
-            false, // is_signer
-            true,
-        )?])
+        // index 0-3 are the accounts of the transfer, 4 is this list
+        Ok(vec![
+            // 5: the vault program, the PDAs below are derived from it
+            ExtraAccountMeta::new_with_pubkey(&week1_challenge::ID, false, false)?,
+            // 6: vault state, seeds = [mint, "vault"]
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::AccountKey { index: 1 },
+                    Seed::Literal {
+                        bytes: week1_challenge::VAULT_SEED.to_vec(),
+                    },
+                ],
+                false, // is_signer
+                true,  // is_writable, credit_transfer books direct deposits on it
+            )?,
+            // 7: whitelist entry of the source token owner, seeds = ["whitelist_entry", vault, owner]
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::Literal {
+                        bytes: week1_challenge::WHITELIST_ENTRY_SEED.to_vec(),
+                    },
+                    Seed::AccountKey { index: 6 },
+                    // owner field of the source token account
+                    Seed::AccountData {
+                        account_index: 0,
+                        data_index: 32,
+                        length: 32,
+                    },
+                ],
+                false, // is_signer
+                true,  // is_writable
+            )?,
+            // 8: the vault's whitelist, seeds = ["whitelist", mint]
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::Literal {
+                        bytes: week1_challenge::WHITELIST_SEED.to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false, // is_signer
+                false, // is_writable
+            )?,
+            // 9: the vault's share mint, seeds = ["shares", vault]
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::Literal {
+                        bytes: week1_challenge::SHARE_MINT_SEED.to_vec(),
+                    },
+                    Seed::AccountKey { index: 6 },
+                ],
+                false, // is_signer
+                false, // is_writable
+            )?,
+            // 10: this program's PDA that signs credit_transfer, seeds = ["hook_authority"]
+            ExtraAccountMeta::new_with_seeds(
+                &[Seed::Literal {
+                    bytes: week1_challenge::HOOK_AUTHORITY_SEED.to_vec(),
+                }],
+                false, // is_signer
+                false, // is_writable
+            )?,
+        ])
     }
 }
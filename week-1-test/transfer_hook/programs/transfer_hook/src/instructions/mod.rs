@@ -1,7 +1,9 @@
 pub mod init_extra_accounts_meta;
 pub mod initialize;
 pub mod transfer_hook;
+pub mod update_extra_accounts_meta;
 
 pub use init_extra_accounts_meta::*;
 pub use initialize::*;
 pub use transfer_hook::*;
+pub use update_extra_accounts_meta::*;
@@ -15,7 +15,13 @@ use anchor_spl::{
         TokenAccount
     }
 };
-use week1_challenge::{ID as week1_ID, Whitelist};
+use week1_challenge::{
+    cpi::{accounts::CreditTransfer, credit_transfer},
+    ID as week1_ID, Vault, Whitelist, WhitelistEntry, HOOK_AUTHORITY_SEED, SHARE_MINT_SEED,
+    VAULT_SEED, WHITELIST_ENTRY_SEED, WHITELIST_SEED,
+};
+
+use crate::error::ErrorCode;
 
 
 #[derive(Accounts)]
@@ -38,39 +44,97 @@ pub struct TransferHook<'info> {
         bump,
     )]
     pub extra_account_meta_list: UncheckedAccount<'info>,
+    /// CHECK: week1 program, the PDAs below are derived from it and direct deposits are credited there
+    #[account(address = week1_ID)]
+    pub vault_program: UncheckedAccount<'info>,
     #[account(
-        seeds = [b"whitelist"], 
-        bump = whitelist.whitelist_bump,
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED], 
+        bump = vault_state.vault_bump,
+        seeds::program = week1_ID
+    )]
+    pub vault_state: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), source_token.owner.as_ref()], 
+        bump = whitelist_entry.bump,
         seeds::program = week1_ID
     )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    #[account(
+        seeds = [WHITELIST_SEED, mint.key().as_ref()], 
+        bump = whitelist.whitelist_bump,
+        seeds::program = week1_ID,
+        constraint = whitelist.admin == vault_state.owner @ErrorCode::WhitelistAdminMismatch
+    )]
     pub whitelist: Account<'info, Whitelist>,
+    /// CHECK: week1 share mint, only read by credit_transfer
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+        seeds::program = week1_ID
+    )]
+    pub share_mint: UncheckedAccount<'info>,
+    /// CHECK: signs credit_transfer, holds nothing
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED],
+        bump,
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
 }
 
 impl<'info> TransferHook<'info> {
     /// This function is called when the transfer hook is executed.
-    pub fn transfer_hook(&mut self, _amount: u64) -> Result<()> {
+    pub fn transfer_hook(&mut self, amount: u64, bumps: &TransferHookBumps) -> Result<()> {
         // Fail this instruction if it is not called from within a transfer hook
         self.check_is_transferring()?;
 
-         let whitelist_accounts = &mut self.whitelist;
+        require!(self.whitelist_entry.is_whitelisted, ErrorCode::OwnerNotWhitelisted);
+        require!(
+            !self.whitelist_entry.is_expired(Clock::get()?.unix_timestamp),
+            ErrorCode::WhitelistExpired
+        );
 
-        if !whitelist_accounts.contains_address(&self.source_token.key()) {
-            panic!("TransferHook: Owner is not whitelisted");
-        };
+        // deposits and withdrawals move through vault_ata and are paused by the vault itself
+        let vault_key = self.vault_state.key();
+        let is_vault_transfer =
+            self.source_token.owner == vault_key || self.destination_token.owner == vault_key;
+        require!(
+            is_vault_transfer || !self.vault_state.paused_transfers,
+            ErrorCode::TransfersPaused
+        );
 
-        let is_actually_whitelisted = self.whitelist.is_whitelisted(&self.source_token.key());
-
-        let is_whitelisted =  match is_actually_whitelisted {
-            Some(yes_whitelisted) => yes_whitelisted == &true && true,
-            None => {false}
-        };
-
-        if !is_whitelisted {
-            panic!("TransferHook: Owner is not whitelisted");
+        // a plain transfer into the vault counts as a deposit. The vault program flags its own
+        // transfers, it books them itself and can't be re-entered from here
+        let is_direct_deposit = self.destination_token.owner == vault_key
+            && self.source_token.owner != vault_key
+            && !self.vault_state.vault_transfer;
+        if is_direct_deposit {
+            self.credit_depositor(amount, bumps.hook_authority)?;
         }
+
         Ok(())
     }
 
+    fn credit_depositor(&self, amount: u64, bump: u8) -> Result<()> {
+        let seeds: &[&[u8]] = &[HOOK_AUTHORITY_SEED, &[bump]];
+        credit_transfer(
+            CpiContext::new_with_signer(
+                self.vault_program.to_account_info(),
+                CreditTransfer {
+                    hook_authority: self.hook_authority.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    vault_state: self.vault_state.to_account_info(),
+                    vault_ata: self.destination_token.to_account_info(),
+                    share_mint: self.share_mint.to_account_info(),
+                    whitelist_entry: self.whitelist_entry.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )
+    }
+
     /// Checks if the transfer hook is being executed during a transfer operation.
     fn check_is_transferring(&mut self) -> Result<()> {
         // Ensure that the source token account has the transfer hook extension enabled
@@ -0,0 +1,66 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    token_2022::spl_token_2022::extension::transfer_hook::TransferHook,
+    token_interface::{get_mint_extension_data, Mint},
+};
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+use crate::error::ErrorCode;
+use crate::InitializeExtraAccountMetaList;
+
+// same order as the interface's update instruction, the system program tops up rent
+#[derive(Accounts)]
+pub struct UpdateExtraAccountMetaList<'info> {
+    /// CHECK: ExtraAccountMetaList Account, rewritten in the handler
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump,
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateExtraAccountMetaList<'info> {
+    /// Replaces a list written by an older version of this program with the current metas,
+    /// transfers of the mint fail against the old list until this has run
+    pub fn update_extra_account_meta_list(&mut self) -> Result<()> {
+        let hook = get_mint_extension_data::<TransferHook>(&self.mint.to_account_info())?;
+        require!(
+            Option::<Pubkey>::from(hook.authority) == Some(self.authority.key()),
+            ErrorCode::NotHookAuthority
+        );
+
+        let extra_account_metas = InitializeExtraAccountMetaList::extra_account_metas()?;
+        let space = ExtraAccountMetaList::size_of(extra_account_metas.len())?;
+
+        let list = self.extra_account_meta_list.to_account_info();
+        let top_up = Rent::get()?.minimum_balance(space).saturating_sub(list.lamports());
+        if top_up > 0 {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.authority.to_account_info(),
+                        to: list.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        list.resize(space)?;
+
+        ExtraAccountMetaList::update::<ExecuteInstruction>(
+            &mut list.try_borrow_mut_data()?,
+            &extra_account_metas,
+        )?;
+        Ok(())
+    }
+}
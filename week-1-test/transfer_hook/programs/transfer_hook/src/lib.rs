@@ -13,6 +13,7 @@ use spl_discriminator::SplDiscriminate;
 use spl_tlv_account_resolution::state::ExtraAccountMetaList;
 use spl_transfer_hook_interface::instruction::{
     ExecuteInstruction, InitializeExtraAccountMetaListInstruction,
+    UpdateExtraAccountMetaListInstruction,
 };
 
 declare_id!("Augb2132S5P1yXCYj7nNZTyksUhCA3k7G5z8SS3o8geh");
@@ -23,7 +24,7 @@ pub mod transfer_hook {
 
     #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
     pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
-        ctx.accounts.transfer_hook(amount)
+        ctx.accounts.transfer_hook(amount, &ctx.bumps)
     }
 
     #[instruction(discriminator = InitializeExtraAccountMetaListInstruction::SPL_DISCRIMINATOR_SLICE)]
@@ -44,4 +45,10 @@ pub mod transfer_hook {
 
         Ok(())
     }
+
+    // rewrites the list with the current metas, signed by the mint's transfer hook authority
+    #[instruction(discriminator = UpdateExtraAccountMetaListInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn update_extra_account_meta_list(ctx: Context<UpdateExtraAccountMetaList>) -> Result<()> {
+        ctx.accounts.update_extra_account_meta_list()
+    }
 }
@@ -0,0 +1,25 @@
+//! Builds the claim_whitelist Merkle tree from a csv of addresses.
+//!
+//! cargo run --example whitelist_tree -- allowlist.csv
+//!
+//! Prints the root for set_whitelist_root, then one line per address with its proof.
+
+use week1_challenge::merkle::MerkleTree;
+
+fn hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("usage: whitelist_tree <allowlist.csv>");
+    let csv = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let tree = MerkleTree::from_csv(&csv).unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+    println!("root {}", hex(&tree.root()));
+    for address in tree.addresses() {
+        let proof: Vec<String> = tree.proof(address).unwrap().iter().map(hex).collect();
+        println!("{} {}", address, proof.join(","));
+    }
+}
@@ -2,3 +2,35 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+#[constant]
+pub const WHITELIST_SEED: &[u8] = b"whitelist";
+
+#[constant]
+pub const WHITELIST_ENTRY_SEED: &[u8] = b"whitelist_entry";
+
+#[constant]
+pub const SHARE_MINT_SEED: &[u8] = b"shares";
+
+#[constant]
+pub const WITHDRAW_REQUEST_SEED: &[u8] = b"withdraw_request";
+
+// vault-owned account that harvest_fees sweeps into, kept apart from vault_ata
+#[constant]
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+// PDA of the transfer hook program that signs credit_transfer
+#[constant]
+pub const HOOK_AUTHORITY_SEED: &[u8] = b"hook_authority";
+
+// the only hook allowed to credit deposits, copied since the hook builds against this crate.
+// The tests check it against transfer_hook::ID
+pub const TRANSFER_HOOK_PROGRAM_ID: Pubkey = pubkey!("Augb2132S5P1yXCYj7nNZTyksUhCA3k7G5z8SS3o8geh");
+
+// entries per add_many/remove_many call, keeps the accounts and args within one transaction
+#[constant]
+pub const MAX_WHITELIST_BATCH: u8 = 10;
+
+// current Vault layout, see migrate_vault
+#[constant]
+pub const VAULT_VERSION: u8 = 1;
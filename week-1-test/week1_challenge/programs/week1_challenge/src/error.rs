@@ -26,4 +26,82 @@ pub enum VaultError {
     AdditionAtUpdateUserOverflow,
     #[msg("underflow at subtracting user amount")]
     SubtractionAtUpdateUserUnderflow,
+    #[msg("Migration accounts must come in [user_ata, whitelist_entry] pairs")]
+    MigrationAccountsMismatch,
+    #[msg("Whitelist entry does not match the token account owner")]
+    WrongWhitelistEntry,
+    #[msg("Amount is too small to be worth any shares")]
+    ZeroShares,
+    #[msg("overflow converting between assets and shares")]
+    ShareMathOverflow,
+    #[msg("Vault has a withdrawal cooldown, use request_withdraw")]
+    CooldownActive,
+    #[msg("Withdrawal is still cooling down")]
+    WithdrawalLocked,
+    #[msg("Cooldown can't be negative")]
+    InvalidCooldown,
+    #[msg("Deposit would exceed the user's cap")]
+    UserCapExceeded,
+    #[msg("Deposit would exceed the vault's TVL cap")]
+    TvlCapExceeded,
+    #[msg("Deposits are paused")]
+    DepositsPaused,
+    #[msg("Withdrawals are paused")]
+    WithdrawalsPaused,
+    #[msg("Only the pauser or the owner can pause the vault")]
+    NotPauser,
+    #[msg("Signer is not the proposed admin")]
+    NotPendingAdmin,
+    #[msg("Vault mint has no token metadata")]
+    MissingMetadata,
+    #[msg("Mint would exceed the vault's max supply")]
+    MaxSupplyExceeded,
+    #[msg("Mint would exceed what the emission schedule has released")]
+    EmissionExceeded,
+    #[msg("Recipient is not whitelisted on this vault")]
+    RecipientNotWhitelisted,
+    #[msg("User is not whitelisted on this vault")]
+    UserNotWhitelisted,
+    #[msg("Signer is not the transfer hook's authority")]
+    NotHookAuthority,
+    #[msg("Whitelist batch is empty or larger than MAX_WHITELIST_BATCH")]
+    InvalidBatchSize,
+    #[msg("Remaining accounts must be one whitelist entry per address")]
+    BatchAccountsMismatch,
+    #[msg("Whitelist expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Whitelist entry has not expired")]
+    EntryNotExpired,
+    #[msg("Whitelist entry still holds a balance")]
+    EntryHasBalance,
+    #[msg("Vault has no whitelist Merkle root")]
+    WhitelistRootNotSet,
+    #[msg("Merkle proof does not match the whitelist root")]
+    InvalidMerkleProof,
+    #[msg("Recorded liabilities overflow")]
+    LiabilitiesOverflow,
+    #[msg("Flash fee can't exceed 10000 basis points")]
+    InvalidFlashFee,
+    #[msg("Flash loans are disabled on this vault")]
+    FlashLoansDisabled,
+    #[msg("A flash loan is already outstanding")]
+    FlashLoanActive,
+    #[msg("No flash loan to repay")]
+    NoFlashLoan,
+    #[msg("flash_borrow must be followed by a flash_repay for the same vault")]
+    MissingFlashRepay,
+    #[msg("Flash loan was not repaid with its fee")]
+    FlashRepayShort,
+    #[msg("Vault is already at the current version")]
+    VaultAlreadyMigrated,
+    #[msg("Account is not a vault of this program")]
+    NotAVault,
+    #[msg("The default pubkey can't be proposed as admin")]
+    InvalidPendingAdmin,
+    #[msg("No admin transfer is pending")]
+    NoPendingAdmin,
+    #[msg("Credited amount is more than vault_ata holds outside the books")]
+    CreditExceedsBalance,
+    #[msg("The vault's own whitelist entry can't be removed")]
+    VaultEntryRequired,
 }
@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::{set_authority, spl_token_2022::instruction::AuthorityType, SetAuthority},
+    token_interface::{spl_pod::option::Nullable, Mint, TokenInterface},
+};
+
+use crate::{error::VaultError, Vault, Whitelist, VAULT_SEED, WHITELIST_SEED};
+
+// mint authorities that follow the vault owner, minting itself stays with the vault PDA
+const OWNER_AUTHORITIES: [AuthorityType; 1] = [AuthorityType::TransferHookProgramId];
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @VaultError::NotAdmin,
+        has_one = mint @VaultError::WrongMint,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ProposeAdmin<'info> {
+    /// Hands the owner-held mint authorities to the vault PDA until `new_owner` accepts,
+    /// proposing again just replaces the pending owner
+    pub fn propose_admin(&mut self, new_owner: Pubkey) -> Result<()> {
+        // an unset pending_owner means no proposal, the authorities would be stuck on the vault
+        require!(!Pubkey::is_none(&new_owner), VaultError::InvalidPendingAdmin);
+        if Pubkey::is_none(&self.vault_state.pending_owner) {
+            for authority_type in OWNER_AUTHORITIES {
+                set_authority(
+                    CpiContext::new(
+                        self.token_program.to_account_info(),
+                        SetAuthority {
+                            current_authority: self.owner.to_account_info(),
+                            account_or_mint: self.mint.to_account_info(),
+                        },
+                    ),
+                    authority_type,
+                    Some(self.vault_state.key()),
+                )?;
+            }
+        }
+
+        self.vault_state.pending_owner = new_owner;
+        Ok(())
+    }
+
+    /// Withdraws the proposal and hands the authorities back to the owner
+    pub fn cancel_admin(&mut self) -> Result<()> {
+        require!(
+            !Pubkey::is_none(&self.vault_state.pending_owner),
+            VaultError::NoPendingAdmin
+        );
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        for authority_type in OWNER_AUTHORITIES {
+            set_authority(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    SetAuthority {
+                        current_authority: self.vault_state.to_account_info(),
+                        account_or_mint: self.mint.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                authority_type,
+                Some(self.owner.key()),
+            )?;
+        }
+
+        self.vault_state.pending_owner = Pubkey::default();
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub new_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = vault_state.pending_owner == new_owner.key() @VaultError::NotPendingAdmin,
+        has_one = mint @VaultError::WrongMint,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        bump = whitelist.whitelist_bump,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> AcceptAdmin<'info> {
+    pub fn accept_admin(&mut self) -> Result<()> {
+        let new_owner = self.new_owner.key();
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        for authority_type in OWNER_AUTHORITIES {
+            set_authority(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    SetAuthority {
+                        current_authority: self.vault_state.to_account_info(),
+                        account_or_mint: self.mint.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                authority_type,
+                Some(new_owner),
+            )?;
+        }
+
+        let vault = &mut self.vault_state;
+        // a pauser left at the old owner would keep their emergency stop
+        if vault.pauser == vault.owner {
+            vault.pauser = new_owner;
+        }
+        vault.owner = new_owner;
+        vault.pending_owner = Pubkey::default();
+        self.whitelist.admin = new_owner;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::error::VaultError;
+use crate::{merkle, Vault, WhitelistEntry, WHITELIST_ENTRY_SEED};
+
+#[derive(Accounts)]
+pub struct ClaimWhitelist<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub vault: Account<'info, Vault>,
+
+    // init, so membership is only claimed once
+    #[account(
+        init,
+        payer = user,
+        seeds = [WHITELIST_ENTRY_SEED, vault.key().as_ref(), user.key().as_ref()],
+        space = WhitelistEntry::DISCRIMINATOR.len() + WhitelistEntry::INIT_SPACE,
+        bump,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimWhitelist<'info> {
+    pub fn claim_whitelist(&mut self, proof: Vec<[u8; 32]>, bumps: &ClaimWhitelistBumps) -> Result<()> {
+        let root = self.vault.whitelist_root.ok_or(VaultError::WhitelistRootNotSet)?;
+        require!(
+            merkle::verify_proof(&root, &self.user.key(), &proof),
+            VaultError::InvalidMerkleProof
+        );
+
+        self.whitelist_entry.set_inner(WhitelistEntry {
+            vault: self.vault.key(),
+            owner: self.user.key(),
+            amount: 0,
+            is_whitelisted: true,
+            bump: bumps.whitelist_entry,
+            cap: None,
+            pending_shares: 0,
+            expires_at: None,
+        });
+        Ok(())
+    }
+}
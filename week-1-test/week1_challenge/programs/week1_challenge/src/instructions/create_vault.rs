@@ -1,26 +1,43 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{create_account, CreateAccount},
+};
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token_interface::{spl_pod::option::Nullable, Mint, TokenAccount, TokenInterface},
+    associated_token::{self, get_associated_token_address_with_program_id, AssociatedToken},
+    token_2022::{
+        initialize_mint2, thaw_account,
+        spl_token_2022::{
+            extension::ExtensionType, state::AccountState, state::Mint as SplMint,
+        },
+        InitializeMint2, ThawAccount,
+    },
+    token_interface::{
+        default_account_state_initialize, interest_bearing_mint_initialize,
+        metadata_pointer_initialize, permanent_delegate_initialize, spl_pod::option::Nullable,
+        spl_pod::optional_keys::OptionalNonZeroPubkey,
+        spl_token_metadata_interface::state::TokenMetadata, token_metadata_initialize,
+        token_metadata_update_field, TokenMetadataUpdateField,
+        transfer_fee_initialize, transfer_hook_initialize, DefaultAccountStateInitialize,
+        InterestBearingMintInitialize, Mint, MetadataPointerInitialize,
+        PermanentDelegateInitialize, TokenInterface, TokenMetadataInitialize,
+        TransferFeeInitialize, TransferHookInitialize,
+    },
 };
 
-use crate::VAULT_SEED;
-use crate::{error::VaultError, Vault};
+use crate::{
+    metadata_field, SHARE_MINT_SEED, VAULT_SEED, VAULT_VERSION, WHITELIST_ENTRY_SEED, WHITELIST_SEED,
+};
+use crate::{error::VaultError, Vault, VaultMintConfig, Whitelist, WhitelistEntry};
 
 #[derive(Accounts)]
+#[instruction(config: VaultMintConfig)]
 pub struct VaultOperation<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
-    #[account(
-        init,
-        payer = owner,
-        mint::decimals = 9,
-        mint::authority = owner,
-        extensions::transfer_hook::authority = owner,
-        extensions::transfer_hook::program_id = hook_program_id.key(), // to add transfer hook program id
-    )]
-    pub mint: InterfaceAccount<'info, Mint>,
+    // created in the handler, the extension set depends on the config
+    #[account(mut)]
+    pub mint: Signer<'info>,
 
     /// CHECK: Program id of the tf hook
     pub hook_program_id: UncheckedAccount<'info>,
@@ -34,14 +51,44 @@ pub struct VaultOperation<'info> {
     )]
     pub vault_state: Account<'info, Vault>,
 
+    // receipt token for deposits, only the vault can mint it
+    #[account(
+        init,
+        payer = owner,
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump,
+        mint::decimals = config.decimals,
+        mint::authority = vault_state,
+        mint::token_program = token_program,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // every vault gets its own whitelist, owned by the vault admin
+    #[account(
+        init,
+        payer = owner,
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        space = Whitelist::space_for(0),
+        bump
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    // the vault's own entry, lets the hook approve withdrawals out of vault_ata like any other sender
     #[account(
         init,
         payer = owner,
-        associated_token::mint = mint,
-        associated_token::authority = vault_state,
-        associated_token::token_program = token_program
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), vault_state.key().as_ref()],
+        space = WhitelistEntry::DISCRIMINATOR.len() + WhitelistEntry::INIT_SPACE,
+        bump
     )]
-    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+    pub vault_entry: Account<'info, WhitelistEntry>,
+
+    /// CHECK: created in the handler once the mint exists
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(&vault_state.key(), &mint.key(), &token_program.key()),
+    )]
+    pub vault_ata: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
@@ -49,7 +96,7 @@ pub struct VaultOperation<'info> {
 }
 
 impl<'info> VaultOperation<'info> {
-    pub fn create_vault(&mut self, bumps: &VaultOperationBumps) -> Result<()> {
+    pub fn create_vault(&mut self, config: VaultMintConfig, bumps: &VaultOperationBumps) -> Result<()> {
         // check that vault does not already exist
         require!(
             Pubkey::is_none(&self.vault_state.owner),
@@ -64,8 +111,270 @@ impl<'info> VaultOperation<'info> {
             mint,
             owner,
             vault_bump,
+            share_mint_bump: bumps.share_mint,
+            total_assets: 0,
+            pending_withdrawals: 0,
+            withdraw_cooldown: 0,
+            tvl_cap: None,
+            user_cap: None,
+            pauser: owner,
+            paused_deposits: false,
+            paused_withdrawals: false,
+            paused_transfers: false,
+            pending_owner: Pubkey::default(),
+            max_supply: config.max_supply,
+            emission: config.emission,
+            total_minted: 0,
+            pending_shares: 0,
+            whitelist_root: None,
+            last_reconciliation: None,
+            flash_fee_bps: None,
+            flash_loan: None,
+            vault_transfer: false,
+            version: VAULT_VERSION,
+        });
+
+        self.whitelist.set_inner(Whitelist {
+            address: Vec::new(),
+            whitelist_bump: bumps.whitelist,
+            admin: owner,
+        });
+
+        let vault_key = self.vault_state.key();
+        self.vault_entry.set_inner(WhitelistEntry {
+            vault: vault_key,
+            owner: vault_key,
+            amount: 0,
+            is_whitelisted: true,
+            bump: bumps.vault_entry,
+            cap: None,
+            pending_shares: 0,
+            expires_at: None,
         });
 
+        self.init_mint(&config)?;
+        self.init_vault_ata(config.default_frozen)?;
+
+        Ok(())
+    }
+
+    fn init_mint(&self, config: &VaultMintConfig) -> Result<()> {
+        let token_program = self.token_program.to_account_info();
+        let mint = self.mint.to_account_info();
+        let owner = self.owner.key();
+        let vault_key = self.vault_state.key();
+
+        let mut extensions = vec![ExtensionType::TransferHook];
+        if config.transfer_fee.is_some() {
+            extensions.push(ExtensionType::TransferFeeConfig);
+        }
+        if config.metadata.is_some() {
+            extensions.push(ExtensionType::MetadataPointer);
+        }
+        if config.interest_rate.is_some() {
+            extensions.push(ExtensionType::InterestBearingConfig);
+        }
+        if config.default_frozen {
+            extensions.push(ExtensionType::DefaultAccountState);
+        }
+        if config.permanent_delegate.is_some() {
+            extensions.push(ExtensionType::PermanentDelegate);
+        }
+        let space = ExtensionType::try_calculate_account_len::<SplMint>(&extensions)?;
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint_key = mint.key();
+        let seeds: &[&[u8]] = &[mint_key.as_ref(), VAULT_SEED, bump_slice];
+
+        // token metadata is written after initialize_mint2 and grows the account, fund it up front
+        let metadata_space = match &config.metadata {
+            Some(metadata) => TokenMetadata {
+                update_authority: OptionalNonZeroPubkey::try_from(Some(vault_key))?,
+                mint: mint.key(),
+                name: metadata.name.clone(),
+                symbol: metadata.symbol.clone(),
+                uri: metadata.uri.clone(),
+                additional_metadata: metadata.additional_fields.clone(),
+            }
+            .tlv_size_of()?,
+            None => 0,
+        };
+
+        create_account(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                CreateAccount {
+                    from: self.owner.to_account_info(),
+                    to: mint.clone(),
+                },
+            ),
+            Rent::get()?.minimum_balance(space + metadata_space),
+            space as u64,
+            &self.token_program.key(),
+        )?;
+
+        // extensions have to be initialised before the mint itself
+        transfer_hook_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                TransferHookInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            Some(owner),
+            Some(self.hook_program_id.key()),
+        )?;
+
+        if let Some(fee) = &config.transfer_fee {
+            // the vault manages fees and collects what is withheld
+            transfer_fee_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    TransferFeeInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.clone(),
+                    },
+                ),
+                Some(&vault_key),
+                Some(&vault_key),
+                fee.basis_points,
+                fee.maximum_fee,
+            )?;
+        }
+
+        // the vault is the metadata authority so update_metadata keeps working after admin rotation
+        if config.metadata.is_some() {
+            metadata_pointer_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    MetadataPointerInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.clone(),
+                    },
+                ),
+                Some(vault_key),
+                Some(mint.key()),
+            )?;
+        }
+
+        // rate and freeze authorities sit with the vault too, so they follow the admin on rotation
+        if let Some(rate) = config.interest_rate {
+            interest_bearing_mint_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    InterestBearingMintInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.clone(),
+                    },
+                ),
+                Some(vault_key),
+                rate,
+            )?;
+        }
+
+        if config.default_frozen {
+            default_account_state_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    DefaultAccountStateInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.clone(),
+                    },
+                ),
+                &AccountState::Frozen,
+            )?;
+        }
+
+        if let Some(delegate) = &config.permanent_delegate {
+            permanent_delegate_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    PermanentDelegateInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.clone(),
+                    },
+                ),
+                delegate,
+            )?;
+        }
+
+        // only the vault mints, through mint_token
+        initialize_mint2(
+            CpiContext::new(token_program.clone(), InitializeMint2 { mint: mint.clone() }),
+            config.decimals,
+            &vault_key,
+            config.default_frozen.then_some(&vault_key),
+        )?;
+
+        if let Some(metadata) = &config.metadata {
+            token_metadata_initialize(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    TokenMetadataInitialize {
+                        program_id: token_program.clone(),
+                        metadata: mint.clone(),
+                        update_authority: self.vault_state.to_account_info(),
+                        mint_authority: self.vault_state.to_account_info(),
+                        mint: mint.clone(),
+                    },
+                    &[seeds],
+                ),
+                metadata.name.clone(),
+                metadata.symbol.clone(),
+                metadata.uri.clone(),
+            )?;
+
+            for (key, value) in &metadata.additional_fields {
+                token_metadata_update_field(
+                    CpiContext::new_with_signer(
+                        token_program.clone(),
+                        TokenMetadataUpdateField {
+                            program_id: token_program.clone(),
+                            metadata: mint.clone(),
+                            update_authority: self.vault_state.to_account_info(),
+                        },
+                        &[seeds],
+                    ),
+                    metadata_field(key),
+                    value.clone(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn init_vault_ata(&self, default_frozen: bool) -> Result<()> {
+        associated_token::create(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: self.owner.to_account_info(),
+                associated_token: self.vault_ata.to_account_info(),
+                authority: self.vault_state.to_account_info(),
+                mint: self.mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        ))?;
+
+        // the vault's own account can't stay frozen
+        if default_frozen {
+            let bump_slice = &[self.vault_state.vault_bump];
+            let mint = self.mint.key();
+            let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+            thaw_account(CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                ThawAccount {
+                    account: self.vault_ata.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    authority: self.vault_state.to_account_info(),
+                },
+                &[seeds],
+            ))?;
+        }
+
         Ok(())
     }
 
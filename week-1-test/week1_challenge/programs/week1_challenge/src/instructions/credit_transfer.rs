@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{
+        get_mint_extension_data, mint_to, Mint, MintTo, TokenAccount, TokenInterface,
+    },
+};
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+
+use crate::{
+    error::VaultError, Vault, WhitelistEntry, HOOK_AUTHORITY_SEED, SHARE_MINT_SEED,
+    TRANSFER_HOOK_PROGRAM_ID, VAULT_SEED, WHITELIST_ENTRY_SEED,
+};
+
+/// Called by the transfer hook when tokens reach vault_ata through a plain transfer.
+/// Token-2022 is already on the stack there, so the shares are only booked and
+/// get minted on the depositor's next vault call or by claim_shares.
+#[derive(Accounts)]
+pub struct CreditTransfer<'info> {
+    // the owner controls the mint's hook program id, so the signer is pinned to our hook
+    #[account(
+        address = Pubkey::find_program_address(&[HOOK_AUTHORITY_SEED], &TRANSFER_HOOK_PROGRAM_ID).0
+            @VaultError::NotHookAuthority,
+    )]
+    pub hook_authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    // any account of the vault, only vault_ata backs shares. The treasury is skipped in the handler
+    #[account(
+        token::mint = mint,
+        token::authority = vault_state,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    // only read, the supply prices the booked shares
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), whitelist_entry.owner.as_ref()],
+        bump = whitelist_entry.bump,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+}
+
+impl<'info> CreditTransfer<'info> {
+    pub fn credit_transfer(&mut self, amount: u64) -> Result<()> {
+        let mint_info = self.mint.to_account_info();
+        let vault_ata = get_associated_token_address_with_program_id(
+            &self.vault_state.key(),
+            &self.mint.key(),
+            mint_info.owner,
+        );
+        if self.vault_ata.key() != vault_ata {
+            return Ok(());
+        }
+
+        require!(!self.vault_state.paused_deposits, VaultError::DepositsPaused);
+        require!(self.whitelist_entry.is_whitelisted, VaultError::UserNotWhitelisted);
+
+        // the hook sees the gross amount, the fee stays withheld on vault_ata
+        let fee = match get_mint_extension_data::<TransferFeeConfig>(&mint_info) {
+            Ok(fee_config) => fee_config
+                .calculate_epoch_fee(Clock::get()?.epoch, amount)
+                .ok_or(VaultError::ShareMathOverflow)?,
+            Err(_) => 0,
+        };
+        let received = amount.saturating_sub(fee);
+
+        // the transfer has already landed, it can't book more than vault_ata holds beyond the books
+        let booked = self
+            .vault_state
+            .total_assets
+            .checked_add(self.vault_state.pending_withdrawals)
+            .ok_or(VaultError::LiabilitiesOverflow)?;
+        require!(
+            received <= self.vault_ata.amount.saturating_sub(booked),
+            VaultError::CreditExceedsBalance
+        );
+
+        let shares = self.vault_state.book_deposit(
+            &mut self.whitelist_entry,
+            received,
+            self.share_mint.supply,
+        )?;
+
+        self.whitelist_entry.pending_shares = self
+            .whitelist_entry
+            .pending_shares
+            .checked_add(shares)
+            .ok_or(VaultError::ShareMathOverflow)?;
+        self.vault_state.pending_shares = self
+            .vault_state
+            .pending_shares
+            .checked_add(shares)
+            .ok_or(VaultError::ShareMathOverflow)?;
+
+        msg!("Credited {} for {} pending shares", received, shares);
+        Ok(())
+    }
+}
+
+// mints the shares booked by credit_transfer, for users who only deposit by plain transfer
+#[derive(Accounts)]
+pub struct ClaimShares<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), sender.key().as_ref()],
+        bump = whitelist_entry.bump,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = share_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
+    )]
+    pub user_share_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> ClaimShares<'info> {
+    pub fn claim_shares(&mut self) -> Result<()> {
+        let pending = self.whitelist_entry.pending_shares;
+        require!(pending > 0, VaultError::ZeroShares);
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+        mint_to(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                MintTo {
+                    mint: self.share_mint.to_account_info(),
+                    to: self.user_share_ata.to_account_info(),
+                    authority: self.vault_state.to_account_info(),
+                },
+                &[seeds],
+            ),
+            pending,
+        )?;
+
+        self.whitelist_entry.pending_shares = 0;
+        self.vault_state.pending_shares = self.vault_state.pending_shares.saturating_sub(pending);
+        Ok(())
+    }
+}
@@ -1,15 +1,23 @@
 
-use anchor_lang::{prelude::*, solana_program::program::{invoke, invoke_signed}};
+use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{ Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+    token_interface::{burn, mint_to, Burn, Mint, MintTo, TokenAccount, TokenInterface}
 };
-use spl_token_2022::{onchain,instruction as token_instruction};
+use spl_token_2022::onchain;
 
 
-use crate::{error::VaultError, Vault, Whitelist};
+use crate::{error::VaultError, program::Week1Challenge, Vault, Whitelist, WhitelistEntry, HOOK_AUTHORITY_SEED, SHARE_MINT_SEED, WHITELIST_ENTRY_SEED, WHITELIST_SEED};
 pub const VAULT_SEED: &[u8] = b"vault";
 
+/// Flags the transfers this program makes into vault_ata. The hook can't call back in to book
+/// them, so it skips crediting while the flag is set, also when we run under another program's CPI.
+pub(crate) fn set_vault_transfer(vault_state: &mut Account<Vault>, active: bool) -> Result<()> {
+    vault_state.vault_transfer = active;
+    // the hook reads the account data, not our copy
+    vault_state.exit(&crate::ID)
+}
+
 #[derive(Accounts)]
 pub struct DepositWithdraw<'info> {
     #[account(mut)]
@@ -21,7 +29,6 @@ pub struct DepositWithdraw<'info> {
 
     #[account(
         mut,
-        constraint = mint.mint_authority == Some(owner.key()).into() @VaultError::WrongMintAuthority,
         extensions::transfer_hook::program_id = hook_program_id.key(), 
     )]
     pub mint: InterfaceAccount<'info, Mint>,
@@ -32,189 +39,249 @@ pub struct DepositWithdraw<'info> {
     #[account(
         mut @VaultError::VaultNotCreatedByAdmin,
         seeds = [mint.key().as_ref(),VAULT_SEED],
-        bump = vault_state.vault_bump
+        bump = vault_state.vault_bump,
+        has_one = owner @VaultError::NotAdmin,
     )]
     pub vault_state: Account<'info, Vault>,
 
     #[account(
         mut,
-        constraint = vault_ata.mint == mint.key() @VaultError::WrongMint        
+        associated_token::mint = mint,
+        associated_token::authority = vault_state,
+        associated_token::token_program = token_program,
     )]
     pub vault_ata: InterfaceAccount<'info, TokenAccount>,
 
+    // balances are keyed by the sender's wallet, so the tokens have to come from and go to its own ATA
     #[account(
         mut,
-        constraint = user_ata.mint == mint.key() @VaultError::WrongMint        
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
     )]
     pub user_ata: InterfaceAccount<'info, TokenAccount>,
 
-     #[account(
+    #[account(
         mut,
-        seeds = [b"whitelist"],
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), sender.key().as_ref()],
+        bump = whitelist_entry.bump,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    // writable because the hook lists the source owner's entry as writable
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), vault_state.key().as_ref()],
+        bump = vault_entry.bump,
+    )]
+    pub vault_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
         bump = whitelist.whitelist_bump,
     )]
     pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = share_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
+    )]
+    pub user_share_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
     /// CHECK: Account containing the extra account
     pub extra_account_meta_list: UncheckedAccount<'info>,
 
+    // the hook derives the vault and entry PDAs from this program
+    pub vault_program: Program<'info, Week1Challenge>,
+
+    /// CHECK: signer of the hook's credit_transfer CPI, only passed through
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED],
+        seeds::program = hook_program_id.key(),
+        bump,
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl<'info> DepositWithdraw<'info> {
-    pub fn deposit(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+    // Accounts the transfer hook needs, the token program resolves the ones it wants from here
+    fn hook_accounts(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.extra_account_meta_list.to_account_info(),
+            self.hook_program_id.to_account_info(),
+            self.vault_program.to_account_info(),
+            self.vault_state.to_account_info(),
+            self.whitelist_entry.to_account_info(),
+            self.vault_entry.to_account_info(),
+            self.whitelist.to_account_info(),
+            self.share_mint.to_account_info(),
+            self.hook_authority.to_account_info(),
+        ]
+    }
 
-        require!(self.user_ata.amount >= amount,VaultError::InsufficientBalance);
+    // mints the shares the hook booked for direct transfers by the sender
+    fn settle_pending_shares(&mut self) -> Result<()> {
+        let pending = self.whitelist_entry.pending_shares;
+        if pending == 0 {
+            return Ok(());
+        }
+        self.with_vault_signer(|signer_seeds| {
+            mint_to(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    MintTo {
+                        mint: self.share_mint.to_account_info(),
+                        to: self.user_share_ata.to_account_info(),
+                        authority: self.vault_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                pending,
+            )
+        })?;
+        self.whitelist_entry.pending_shares = 0;
+        self.vault_state.pending_shares = self.vault_state.pending_shares.saturating_sub(pending);
+        self.share_mint.reload()?;
+        self.user_share_ata.reload()?;
+        Ok(())
+    }
 
-        //   1. Manually build the `transfer_checked` instruction provided by the SPL Token program.
-        let mut transfer_ix = token_instruction::transfer_checked(
-            &self.token_program.key(),
-            &self.user_ata.key(),
-            &self.mint.key(),
-            &self.vault_ata.key(),
-            &self.sender.key(),
-            &[], // No multisig signers are needed.
-            amount,
-            self.mint.decimals,
-        )?;
+    // vault PDA seeds, it is the authority of both vault_ata and the share mint
+    fn with_vault_signer<T>(&self, f: impl FnOnce(&[&[&[u8]]]) -> Result<T>) -> Result<T> {
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[
+            mint.as_ref(),
+            VAULT_SEED,
+            bump_slice,
+        ];
+        f(&[seeds])
+    }
 
-        // // 2. Manually add the extra accounts required by the transfer hook.
-        // // The Token 2022 program expects these to follow the core transfer accounts.
-        transfer_ix.accounts.push(AccountMeta::new_readonly(self.extra_account_meta_list.key(), false));
-        transfer_ix.accounts.push(AccountMeta::new(self.whitelist.key(), false));
-        
+    /// Deposits `amount` of the vault token and mints shares at the current exchange rate
+    pub fn deposit(&mut self, amount: u64, _remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+
+        require!(!self.vault_state.paused_deposits, VaultError::DepositsPaused);
+        // membership is separate from the balance, a removed user keeps their entry
+        require!(self.whitelist_entry.is_whitelisted, VaultError::UserNotWhitelisted);
+        self.settle_pending_shares()?;
+        require!(self.user_ata.amount >= amount,VaultError::InsufficientBalance);
 
-        // // 3. Create a flat list of all AccountInfos that the instruction needs.
-        // // This includes all accounts for the core transfer and the hook.
-        let account_infos = &[
+        let balance_before = self.vault_ata.amount;
+        set_vault_transfer(&mut self.vault_state, true)?;
+        onchain::invoke_transfer_checked(
+            &self.token_program.key(),
             self.user_ata.to_account_info(),
             self.mint.to_account_info(),
             self.vault_ata.to_account_info(),
             self.sender.to_account_info(),
-            self.token_program.to_account_info(), // The Token Program must be in this list for `invoke`
-            self.extra_account_meta_list.to_account_info(),
-            self.whitelist.to_account_info(),
-            self.hook_program_id.to_account_info(),
-        ];
+            &self.hook_accounts(),
+            amount,
+            self.mint.decimals,
+            &[],  // No signer seeds needed here
+        )?;
+        set_vault_transfer(&mut self.vault_state, false)?;
 
-        // // 4. Use the low-level `invoke` function to execute the CPI.
-        invoke(&transfer_ix, account_infos)?;
-
-    //     let  account_infos = vec![
-    //     self.user_ata.to_account_info(),          // source
-    //     self.mint.to_account_info(),               // mint
-    //     self.vault_ata.to_account_info(),          // destination
-    //     self.sender.to_account_info(),             // authority
-    //     self.extra_account_meta_list.to_account_info(),
-    //     self.whitelist.to_account_info()
-    //     ];
-
-    // onchain::invoke_transfer_checked(
-    //     &self.token_program.key(),
-    //     self.user_ata.to_account_info(),
-    //     self.mint.to_account_info(),
-    //     self.vault_ata.to_account_info(),
-    //     self.sender.to_account_info(),
-    //     &remaining_accounts.to_vec(),
-    //     amount,
-    //     self.mint.decimals,
-    //     &[],  // No signer seeds needed here
-    // )?;
-
-        // let transfer_accounts = TransferChecked{
-        //      authority: self.sender.to_account_info(),
-        //      from: self.user_ata.to_account_info(),
-        //      mint: self.mint.to_account_info(),
-        //      to: self.vault_ata.to_account_info()
-        // };
+        // a transfer fee is withheld on the vault ata, book only what the vault can use
+        self.vault_ata.reload()?;
+        let received = self.vault_ata.amount.checked_sub(balance_before)
+            .ok_or(VaultError::SubtractionAtUpdateUserUnderflow)?;
 
-        
-        // let  transfer_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts).with_remaining_accounts(account_infos);
+        let shares = self.vault_state.book_deposit(
+            &mut self.whitelist_entry,
+            received,
+            self.share_mint.supply,
+        )?;
 
-        
+        self.with_vault_signer(|signer_seeds| {
+            mint_to(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    MintTo {
+                        mint: self.share_mint.to_account_info(),
+                        to: self.user_share_ata.to_account_info(),
+                        authority: self.vault_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                shares,
+            )
+        })?;
 
-        // transfer_checked(transfer_ctx, amount, self.mint.decimals)?;
-        let user_key = self.user_ata.key();
-    
-    // Try to find existing entry
-    if let Some(entry) = self.whitelist.address.iter_mut().find(|(addr, _, _)| *addr == user_key) {
-        // User exists, add to their existing amount
-        entry.1 = entry.1.checked_add(amount)
-            .ok_or(VaultError::AdditionAtUpdateUserOverflow)?;
-    } 
         Ok(())
     }
 
-    pub fn withdraw(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+    /// Burns `shares` and pays out their value in the vault token
+    pub fn withdraw(&mut self, shares: u64, _remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
 
         // 1. Initial requirement checks
-        let is_user_exist = self.whitelist.contains_address(&self.user_ata.key());
-        require!(is_user_exist, VaultError::UserNotExistInVecForReal);
-
-        let user_deposited_amount = self.whitelist.address
-            .iter()
-            .find(|(addr, _, _)| *addr == self.user_ata.key())
-            .map(|(_, amount, _)| *amount)
-            .unwrap_or(0);
-        require!(user_deposited_amount >= amount, VaultError::InsufficientBalance);
-
-        // 2. Build the `transfer_checked` instruction for withdrawal
-        let mut transfer_ix = token_instruction::transfer_checked(
-            &self.token_program.key(),
-            &self.vault_ata.key(),      // Source
-            &self.mint.key(),           // Mint
-            &self.user_ata.key(),       // Destination
-            &self.vault_state.key(),    // Authority (our PDA)
-            &[],                        // No multi-sig
-            amount,
-            self.mint.decimals,
+        require!(!self.vault_state.paused_withdrawals, VaultError::WithdrawalsPaused);
+        require!(self.vault_state.withdraw_cooldown == 0, VaultError::CooldownActive);
+        self.settle_pending_shares()?;
+        require!(self.user_share_ata.amount >= shares, VaultError::InsufficientBalance);
+
+        let total_shares = self
+            .vault_state
+            .total_shares(self.share_mint.supply)
+            .ok_or(VaultError::ShareMathOverflow)?;
+        let amount = self
+            .vault_state
+            .assets_for_shares(shares, total_shares)
+            .ok_or(VaultError::ShareMathOverflow)?;
+
+        // 2. burn the shares, the sender owns them
+        burn(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                Burn {
+                    mint: self.share_mint.to_account_info(),
+                    from: self.user_share_ata.to_account_info(),
+                    authority: self.sender.to_account_info(),
+                },
+            ),
+            shares,
         )?;
 
-        // 3. Add extra accounts for the transfer hook
-        transfer_ix.accounts.push(AccountMeta::new_readonly(self.extra_account_meta_list.key(), false));
-        transfer_ix.accounts.push(AccountMeta::new(self.whitelist.key(), false));
-        
-
-        // 4. Create the list of AccountInfos for the CPI
-        let account_infos = &[
-            self.vault_ata.to_account_info(),
-            self.mint.to_account_info(),
-            self.user_ata.to_account_info(),
-            self.vault_state.to_account_info(), // The PDA authority
-            self.token_program.to_account_info(),
-            self.extra_account_meta_list.to_account_info(),
-            self.whitelist.to_account_info(),
-            self.hook_program_id.to_account_info(),
-        ];
-
-        // 5. Construct the PDA signer seeds
-        let bump_slice = &[self.vault_state.vault_bump];
-        let mint = self.mint.key();
-        let seeds: &[&[u8]] = &[
-            mint.as_ref(),
-            &VAULT_SEED,
-            bump_slice,
-        ];
-        let signer_seeds = &[&seeds[..]];
-
-        // 6. Use `invoke_signed` to execute the CPI with PDA signature
-        invoke_signed(&transfer_ix, account_infos, signer_seeds)?;
+        // 3. Transfer out of the vault, signed by the vault PDA
+        self.with_vault_signer(|signer_seeds| {
+            onchain::invoke_transfer_checked(
+                &self.token_program.key(),
+                self.vault_ata.to_account_info(),
+                self.mint.to_account_info(),
+                self.user_ata.to_account_info(),
+                self.vault_state.to_account_info(),
+                &self.hook_accounts(),
+                amount,
+                self.mint.decimals,
+                signer_seeds,
+            )
+            .map_err(Into::into)
+        })?;
         msg!("Invoke signed transfer successful for withdraw!");
 
-        // deduct amount from user vec vault balance
-
-        let user_key = self.user_ata.key();
-    if let Some(pos) = self.whitelist.address.iter().position(|(addr, _, _)| *addr == user_key) {
-        let (_, user_balance, _) = &mut self.whitelist.address[pos];
-        *user_balance = user_balance.checked_sub(amount)
+        let vault = &mut self.vault_state;
+        vault.total_assets = vault.total_assets.checked_sub(amount)
             .ok_or(VaultError::SubtractionAtUpdateUserUnderflow)?;
-        
-        // Optionally remove entry if balance is 0
-        if *user_balance == 0 {
-            self.whitelist.address.remove(pos);
-        }
-    }
+
+        // 4. deduct amount from the user's entry, shares may have come from someone else.
+        // any transfer fee is paid out of `amount` by the user, it left the vault in full
+        // the entry stays at zero, the user remains whitelisted and can deposit again
+        let entry = &mut self.whitelist_entry;
+        entry.amount = entry.amount.saturating_sub(amount);
 
         Ok(())
     }
@@ -0,0 +1,219 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::instructions::{
+        load_current_index_checked, load_instruction_at_checked, ID as INSTRUCTIONS_SYSVAR_ID,
+    },
+    Discriminator,
+};
+use anchor_spl::token_interface::{get_mint_extension_data, Mint, TokenAccount, TokenInterface};
+use spl_token_2022::{extension::transfer_fee::TransferFeeConfig, onchain};
+
+use crate::error::VaultError;
+use crate::instructions::{set_vault_transfer, VAULT_SEED};
+use crate::{
+    program::Week1Challenge, Vault, Whitelist, WhitelistEntry, HOOK_AUTHORITY_SEED,
+    SHARE_MINT_SEED, WHITELIST_ENTRY_SEED, WHITELIST_SEED,
+};
+
+// position of vault_state in FlashLoan, flash_borrow looks for it in the repay instruction
+const VAULT_STATE_INDEX: usize = 3;
+
+// shared by flash_borrow and flash_repay, both move tokens through the hook
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        extensions::transfer_hook::program_id = hook_program_id.key(),
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Program id of the tf hook
+    pub hook_program_id: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_state,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = borrower,
+    )]
+    pub borrower_ata: InterfaceAccount<'info, TokenAccount>,
+
+    // the hook checks the source owner's entry, the vault's on borrow and the borrower's on repay
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), vault_state.key().as_ref()],
+        bump = vault_entry.bump,
+    )]
+    pub vault_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), borrower.key().as_ref()],
+        bump = borrower_entry.bump,
+    )]
+    pub borrower_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        bump = whitelist.whitelist_bump,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Account containing the extra account
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub vault_program: Program<'info, Week1Challenge>,
+
+    /// CHECK: introspected for the matching flash_repay
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: signer of the hook's credit_transfer CPI, only passed through
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED],
+        seeds::program = hook_program_id.key(),
+        bump,
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> FlashLoan<'info> {
+    fn hook_accounts(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.extra_account_meta_list.to_account_info(),
+            self.hook_program_id.to_account_info(),
+            self.vault_program.to_account_info(),
+            self.vault_state.to_account_info(),
+            self.vault_entry.to_account_info(),
+            self.borrower_entry.to_account_info(),
+            self.whitelist.to_account_info(),
+            self.share_mint.to_account_info(),
+            self.hook_authority.to_account_info(),
+        ]
+    }
+
+    // a top-level flash_repay for this vault later in the transaction
+    fn repay_follows(&self) -> Result<bool> {
+        let sysvar = self.instructions_sysvar.to_account_info();
+        let current = load_current_index_checked(&sysvar)? as usize;
+        // called through CPI the index points at the caller's instruction, not at this borrow
+        if load_instruction_at_checked(current, &sysvar)?.program_id != crate::ID {
+            return Ok(false);
+        }
+
+        let vault_key = self.vault_state.key();
+        let mut index = current + 1;
+        while let Ok(ix) = load_instruction_at_checked(index, &sysvar) {
+            if ix.program_id == crate::ID
+                && ix.data.starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+                && ix.accounts.get(VAULT_STATE_INDEX).map(|meta| meta.pubkey) == Some(vault_key)
+            {
+                return Ok(true);
+            }
+            index += 1;
+        }
+        Ok(false)
+    }
+
+    pub fn flash_borrow(&mut self, amount: u64) -> Result<()> {
+        require!(self.vault_state.flash_fee_bps.is_some(), VaultError::FlashLoansDisabled);
+        require!(self.vault_state.flash_loan.is_none(), VaultError::FlashLoanActive);
+        require!(!self.vault_state.paused_withdrawals, VaultError::WithdrawalsPaused);
+        require!(self.vault_ata.amount >= amount, VaultError::InsufficientBalance);
+        require!(self.repay_follows()?, VaultError::MissingFlashRepay);
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+        onchain::invoke_transfer_checked(
+            &self.token_program.key(),
+            self.vault_ata.to_account_info(),
+            self.mint.to_account_info(),
+            self.borrower_ata.to_account_info(),
+            self.vault_state.to_account_info(),
+            &self.hook_accounts(),
+            amount,
+            self.mint.decimals,
+            &[seeds],
+        )?;
+
+        self.vault_state.flash_loan = Some(amount);
+        Ok(())
+    }
+
+    pub fn flash_repay(&mut self) -> Result<()> {
+        let loan = self.vault_state.flash_loan.ok_or(VaultError::NoFlashLoan)?;
+        let fee = self
+            .vault_state
+            .flash_fee(loan)
+            .ok_or(VaultError::ShareMathOverflow)?;
+        let due = loan.checked_add(fee).ok_or(VaultError::ShareMathOverflow)?;
+
+        // gross up so vault_ata receives `due` after any transfer fee
+        let mint_info = self.mint.to_account_info();
+        let gross = match get_mint_extension_data::<TransferFeeConfig>(&mint_info) {
+            Ok(fee_config) => fee_config
+                .calculate_inverse_epoch_fee(Clock::get()?.epoch, due)
+                .and_then(|transfer_fee| due.checked_add(transfer_fee))
+                .ok_or(VaultError::ShareMathOverflow)?,
+            Err(_) => due,
+        };
+
+        let balance_before = self.vault_ata.amount;
+        set_vault_transfer(&mut self.vault_state, true)?;
+        onchain::invoke_transfer_checked(
+            &self.token_program.key(),
+            self.borrower_ata.to_account_info(),
+            self.mint.to_account_info(),
+            self.vault_ata.to_account_info(),
+            self.borrower.to_account_info(),
+            &self.hook_accounts(),
+            gross,
+            self.mint.decimals,
+            &[],
+        )?;
+        set_vault_transfer(&mut self.vault_state, false)?;
+
+        self.vault_ata.reload()?;
+        let received = self
+            .vault_ata
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(VaultError::SubtractionAtUpdateUserUnderflow)?;
+        require!(received >= due, VaultError::FlashRepayShort);
+
+        // raising total_assets raises the share price, every holder gains pro-rata
+        let vault = &mut self.vault_state;
+        vault.total_assets = vault
+            .total_assets
+            .checked_add(received - loan)
+            .ok_or(VaultError::AdditionAtUpdateUserOverflow)?;
+        vault.flash_loan = None;
+
+        msg!("Flash loan of {} repaid with {} fee", loan, received - loan);
+        Ok(())
+    }
+}
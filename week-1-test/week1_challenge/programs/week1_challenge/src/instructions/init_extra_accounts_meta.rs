@@ -26,12 +26,64 @@ pub struct InitializeExtraAccountMetaList<'info> {
 
 impl<'info> InitializeExtraAccountMetaList<'info> {
     pub fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
-        Ok(vec![ExtraAccountMeta::new_with_seeds(
-            &[Seed::Literal {
-                bytes: b"whitelist".to_vec(),
-            }],
-            false, // is_signer
-            false, // is_writable
-        )?])
+        Ok(vec![
+            ExtraAccountMeta::new_with_pubkey(&crate::ID, false, false)?,
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::AccountKey { index: 1 },
+                    Seed::Literal {
+                        bytes: crate::VAULT_SEED.to_vec(),
+                    },
+                ],
+                false, // is_signer
+                true,  // is_writable
+            )?,
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::Literal {
+                        bytes: crate::WHITELIST_ENTRY_SEED.to_vec(),
+                    },
+                    Seed::AccountKey { index: 6 },
+                    Seed::AccountData {
+                        account_index: 0,
+                        data_index: 32,
+                        length: 32,
+                    },
+                ],
+                false, // is_signer
+                true,  // is_writable
+            )?,
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::Literal {
+                        bytes: crate::WHITELIST_SEED.to_vec(),
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false, // is_signer
+                false, // is_writable
+            )?,
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[
+                    Seed::Literal {
+                        bytes: crate::SHARE_MINT_SEED.to_vec(),
+                    },
+                    Seed::AccountKey { index: 6 },
+                ],
+                false, // is_signer
+                false, // is_writable
+            )?,
+            ExtraAccountMeta::new_with_seeds(
+                &[Seed::Literal {
+                    bytes: crate::HOOK_AUTHORITY_SEED.to_vec(),
+                }],
+                false, // is_signer
+                false, // is_writable
+            )?,
+        ])
     }
 }
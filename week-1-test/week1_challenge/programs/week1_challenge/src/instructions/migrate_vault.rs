@@ -0,0 +1,182 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    token_2022::{set_authority, spl_token_2022::instruction::AuthorityType, SetAuthority},
+    token_interface::{Mint, TokenInterface},
+};
+
+use crate::error::VaultError;
+use crate::{
+    Vault, VaultV0, Whitelist, WhitelistEntry, SHARE_MINT_SEED, VAULT_SEED, VAULT_VERSION,
+    WHITELIST_ENTRY_SEED, WHITELIST_SEED,
+};
+
+/// Brings a vault created by an older program version up to VAULT_VERSION.
+/// Run it before migrate_whitelist_chunk and withdraw_legacy, both expect the current layout.
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: may still hold an older layout, checked and rewritten in the handler
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump,
+    )]
+    pub vault_state: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump,
+        mint::decimals = mint.decimals,
+        mint::authority = vault_state,
+        mint::token_program = token_program,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // the old global whitelist stays in place until migrate_whitelist_chunk has emptied it
+    #[account(
+        init,
+        payer = owner,
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        space = Whitelist::space_for(0),
+        bump
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), vault_state.key().as_ref()],
+        space = WhitelistEntry::DISCRIMINATOR.len() + WhitelistEntry::INIT_SPACE,
+        bump
+    )]
+    pub vault_entry: Account<'info, WhitelistEntry>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> MigrateVault<'info> {
+    /// Layout version of the account, vaults from before the version field are 0
+    fn stored_version(&self) -> Result<u8> {
+        let info = self.vault_state.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, VaultError::NotAVault);
+
+        let data = info.try_borrow_data()?;
+        require!(data.starts_with(Vault::DISCRIMINATOR), VaultError::NotAVault);
+        if data.len() == Vault::DISCRIMINATOR.len() + VaultV0::INIT_SPACE {
+            return Ok(0);
+        }
+        Ok(Vault::try_deserialize(&mut &data[..])?.version)
+    }
+
+    pub fn migrate_vault(&mut self, bumps: &MigrateVaultBumps) -> Result<()> {
+        let version = self.stored_version()?;
+        require!(version < VAULT_VERSION, VaultError::VaultAlreadyMigrated);
+
+        // only v0 predates the version field, later versions add their steps here
+        let legacy = {
+            let data = self.vault_state.try_borrow_data()?;
+            VaultV0::deserialize(&mut &data[Vault::DISCRIMINATOR.len()..])?
+        };
+        require_keys_eq!(legacy.owner, self.owner.key(), VaultError::NotAdmin);
+        require_keys_eq!(legacy.mint, self.mint.key(), VaultError::WrongMint);
+
+        self.grow_vault()?;
+        let owner = legacy.owner;
+        let vault = Vault {
+            mint: legacy.mint,
+            vault_bump: legacy.vault_bump,
+            owner,
+            share_mint_bump: bumps.share_mint,
+            // legacy balances are booked as their records move over in migrate_whitelist_chunk
+            total_assets: 0,
+            pending_withdrawals: 0,
+            withdraw_cooldown: 0,
+            tvl_cap: None,
+            user_cap: None,
+            pauser: owner,
+            paused_deposits: false,
+            paused_withdrawals: false,
+            paused_transfers: false,
+            pending_owner: Pubkey::default(),
+            max_supply: None,
+            emission: None,
+            total_minted: 0,
+            pending_shares: 0,
+            whitelist_root: None,
+            last_reconciliation: None,
+            flash_fee_bps: None,
+            flash_loan: None,
+            vault_transfer: false,
+            version: VAULT_VERSION,
+        };
+        vault.try_serialize(&mut &mut self.vault_state.try_borrow_mut_data()?[..])?;
+
+        self.whitelist.set_inner(Whitelist {
+            address: Vec::new(),
+            whitelist_bump: bumps.whitelist,
+            admin: owner,
+        });
+
+        let vault_key = self.vault_state.key();
+        self.vault_entry.set_inner(WhitelistEntry {
+            vault: vault_key,
+            owner: vault_key,
+            amount: 0,
+            is_whitelisted: true,
+            bump: bumps.vault_entry,
+            cap: None,
+            pending_shares: 0,
+            expires_at: None,
+        });
+
+        // old mints were minted by the owner directly, mint_token signs as the vault now
+        if self.mint.mint_authority == Some(owner).into() {
+            set_authority(
+                CpiContext::new(
+                    self.token_program.to_account_info(),
+                    SetAuthority {
+                        current_authority: self.owner.to_account_info(),
+                        account_or_mint: self.mint.to_account_info(),
+                    },
+                ),
+                AuthorityType::MintTokens,
+                Some(vault_key),
+            )?;
+        }
+
+        msg!("Vault {} migrated from v{} to v{}", vault_key, version, VAULT_VERSION);
+        Ok(())
+    }
+
+    fn grow_vault(&self) -> Result<()> {
+        let info = self.vault_state.to_account_info();
+        let space = Vault::DISCRIMINATOR.len() + Vault::INIT_SPACE;
+
+        let top_up = Rent::get()?.minimum_balance(space).saturating_sub(info.lamports());
+        if top_up > 0 {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.owner.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        info.resize(space)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{allocate, assign, transfer, Allocate, Assign, Transfer},
+};
+use anchor_spl::token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface};
+use spl_token_2022::onchain;
+
+use crate::error::VaultError;
+use crate::instructions::VAULT_SEED;
+use crate::{
+    program::Week1Challenge, Vault, Whitelist, WhitelistEntry, HOOK_AUTHORITY_SEED,
+    SHARE_MINT_SEED, WHITELIST_ENTRY_SEED, WHITELIST_SEED,
+};
+
+// remaining accounts per migrated record: [user_ata, whitelist_entry, user_share_ata]
+// the share ata has to exist already, anyone can create it beforehand
+pub const ACCOUNTS_PER_RECORD: usize = 3;
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct MigrateWhitelist<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == vault.owner.key() @VaultError::NotAdmin
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [WHITELIST_SEED], // the pre-upgrade global whitelist
+        bump = legacy_whitelist.whitelist_bump,
+    )]
+    pub legacy_whitelist: Account<'info, Whitelist>,
+
+    // migrate_vault has to run first, it brings the vault to the current layout
+    #[account(
+        mut @VaultError::VaultNotCreatedByAdmin,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault.vault_bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref()],
+        bump = vault.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> MigrateWhitelist<'info> {
+    /// Moves the legacy records of the given user ATAs from the whitelist vec into
+    /// their WhitelistEntry PDAs, then shrinks the vec account.
+    pub fn migrate_whitelist_chunk(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            remaining_accounts.len().is_multiple_of(ACCOUNTS_PER_RECORD),
+            VaultError::MigrationAccountsMismatch
+        );
+
+        let vault_key = self.vault.key();
+
+        let mut total_shares = self
+            .vault
+            .total_shares(self.share_mint.supply)
+            .ok_or(VaultError::ShareMathOverflow)?;
+
+        for accounts in remaining_accounts.chunks(ACCOUNTS_PER_RECORD) {
+            let (user_ata, entry_info, user_share_ata) = (&accounts[0], &accounts[1], &accounts[2]);
+
+            // legacy records are keyed by the user's token account
+            let pos = self
+                .legacy_whitelist
+                .address
+                .iter()
+                .position(|(addr, _, _)| *addr == user_ata.key())
+                .ok_or(VaultError::UserNotExistInVec)?;
+
+            // entries are keyed by the wallet, read it from the token account
+            let token_account = TokenAccount::try_deserialize(&mut &user_ata.data.borrow()[..])?;
+            require_keys_eq!(token_account.mint, self.vault.mint, VaultError::WrongMint);
+            let owner = token_account.owner;
+
+            let (expected_entry, entry_bump) = Pubkey::find_program_address(
+                &[WHITELIST_ENTRY_SEED, vault_key.as_ref(), owner.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(entry_info.key(), expected_entry, VaultError::WrongWhitelistEntry);
+
+            let (_, amount, is_whitelisted) = self.legacy_whitelist.address.remove(pos);
+
+            if entry_info.owner == &crate::ID {
+                // the user was already re-added after the upgrade, merge the balances
+                let mut entry = WhitelistEntry::try_deserialize(&mut &entry_info.data.borrow()[..])?;
+                entry.amount = entry
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(VaultError::AdditionAtUpdateUserOverflow)?;
+                entry.is_whitelisted |= is_whitelisted;
+                entry.try_serialize(&mut &mut entry_info.data.borrow_mut()[..])?;
+            } else {
+                let space = WhitelistEntry::DISCRIMINATOR.len() + WhitelistEntry::INIT_SPACE;
+                let signer_seeds: &[&[&[u8]]] = &[&[
+                    WHITELIST_ENTRY_SEED,
+                    vault_key.as_ref(),
+                    owner.as_ref(),
+                    &[entry_bump],
+                ]];
+                // lamports sent to the address beforehand would make create_account fail,
+                // only top it up to rent exemption
+                let top_up = Rent::get()?.minimum_balance(space).saturating_sub(entry_info.lamports());
+                if top_up > 0 {
+                    transfer(
+                        CpiContext::new(
+                            self.system_program.to_account_info(),
+                            Transfer {
+                                from: self.admin.to_account_info(),
+                                to: entry_info.clone(),
+                            },
+                        ),
+                        top_up,
+                    )?;
+                }
+                allocate(
+                    CpiContext::new_with_signer(
+                        self.system_program.to_account_info(),
+                        Allocate { account_to_allocate: entry_info.clone() },
+                        signer_seeds,
+                    ),
+                    space as u64,
+                )?;
+                assign(
+                    CpiContext::new_with_signer(
+                        self.system_program.to_account_info(),
+                        Assign { account_to_assign: entry_info.clone() },
+                        signer_seeds,
+                    ),
+                    &crate::ID,
+                )?;
+
+                WhitelistEntry {
+                    vault: vault_key,
+                    owner,
+                    amount,
+                    is_whitelisted,
+                    bump: entry_bump,
+                    cap: None,
+                    pending_shares: 0,
+                    expires_at: None,
+                }
+                .try_serialize(&mut &mut entry_info.data.borrow_mut()[..])?;
+            }
+            // legacy balances were never backed by shares, issue them now
+            if amount > 0 {
+                let share_account =
+                    TokenAccount::try_deserialize(&mut &user_share_ata.data.borrow()[..])?;
+                require_keys_eq!(share_account.mint, self.share_mint.key(), VaultError::WrongMint);
+                require_keys_eq!(share_account.owner, owner, VaultError::WrongATA);
+
+                let shares = self
+                    .vault
+                    .shares_for_assets(amount, total_shares)
+                    .ok_or(VaultError::ShareMathOverflow)?;
+                self.mint_shares(user_share_ata, shares)?;
+
+                total_shares = total_shares
+                    .checked_add(shares)
+                    .ok_or(VaultError::ShareMathOverflow)?;
+                self.vault.total_assets = self
+                    .vault
+                    .total_assets
+                    .checked_add(amount)
+                    .ok_or(VaultError::AdditionAtUpdateUserOverflow)?;
+            }
+            msg!("Migrated {} with {} deposited", owner, amount);
+        }
+
+        self.shrink_whitelist()
+    }
+
+    fn mint_shares(&self, to: &AccountInfo<'info>, shares: u64) -> Result<()> {
+        let mint = self.vault.mint;
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, &[self.vault.vault_bump]];
+        mint_to(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                MintTo {
+                    mint: self.share_mint.to_account_info(),
+                    to: to.clone(),
+                    authority: self.vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares,
+        )
+    }
+
+    fn shrink_whitelist(&self) -> Result<()> {
+        let account_info = self.legacy_whitelist.to_account_info();
+
+        let new_account_size = Whitelist::space_for(self.legacy_whitelist.address.len());
+        if new_account_size >= account_info.data_len() {
+            return Ok(());
+        }
+
+        let lamports_required = (Rent::get()?).minimum_balance(new_account_size);
+        let rent_diff = account_info.lamports() - lamports_required;
+
+        account_info.resize(new_account_size)?;
+        msg!("Account Size Downgraded: {}", account_info.data_len());
+
+        **self.admin.to_account_info().try_borrow_mut_lamports()? += rent_diff;
+        **account_info.try_borrow_mut_lamports()? -= rent_diff;
+
+        Ok(())
+    }
+}
+
+/// Withdraw for users whose record is still in the legacy whitelist vec,
+/// keeps their deposits reachable until the admin has migrated them.
+/// Needs migrate_vault and the hook's update_extra_account_meta_list to have run.
+#[derive(Accounts)]
+pub struct LegacyWithdraw<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    #[account(
+        mut,
+        extensions::transfer_hook::program_id = hook_program_id.key(),
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Program id of the tf hook
+    pub hook_program_id: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [WHITELIST_SEED], // the pre-upgrade global whitelist
+        bump = legacy_whitelist.whitelist_bump,
+    )]
+    pub legacy_whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    // vault and entry are writable extra metas of the hook, so they have to be here too
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), vault_state.key().as_ref()],
+        bump = vault_entry.bump,
+    )]
+    pub vault_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        bump = whitelist.whitelist_bump,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        mut,
+        constraint = vault_ata.mint == mint.key() @VaultError::WrongMint,
+        constraint = vault_ata.owner == vault_state.key() @VaultError::WrongATA,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_ata.mint == mint.key() @VaultError::WrongMint,
+        constraint = user_ata.owner == sender.key() @VaultError::WrongATA,
+    )]
+    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Account containing the extra account
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub vault_program: Program<'info, Week1Challenge>,
+
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+
+    /// CHECK: passed through to the hook, which signs credit_transfer with it
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED],
+        seeds::program = hook_program_id.key(),
+        bump,
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> LegacyWithdraw<'info> {
+    pub fn withdraw_legacy(&mut self, amount: u64) -> Result<()> {
+        let user_key = self.user_ata.key();
+        let pos = self
+            .legacy_whitelist
+            .address
+            .iter()
+            .position(|(addr, _, _)| *addr == user_key)
+            .ok_or(VaultError::UserNotExistInVecForReal)?;
+        require!(self.legacy_whitelist.address[pos].1 >= amount, VaultError::InsufficientBalance);
+        require!(!self.vault_state.paused_withdrawals, VaultError::WithdrawalsPaused);
+        // same rule as withdraw, with a cooldown the record has to be migrated and go through request_withdraw
+        require!(self.vault_state.withdraw_cooldown == 0, VaultError::CooldownActive);
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        // the hook checks the vault's own entry, the source is the vault ata
+        onchain::invoke_transfer_checked(
+            &self.token_program.key(),
+            self.vault_ata.to_account_info(),
+            self.mint.to_account_info(),
+            self.user_ata.to_account_info(),
+            self.vault_state.to_account_info(),
+            &[
+                self.extra_account_meta_list.to_account_info(),
+                self.hook_program_id.to_account_info(),
+                self.vault_program.to_account_info(),
+                self.vault_state.to_account_info(),
+                self.vault_entry.to_account_info(),
+                self.whitelist.to_account_info(),
+                self.share_mint.to_account_info(),
+                self.hook_authority.to_account_info(),
+            ],
+            amount,
+            self.mint.decimals,
+            &[seeds],
+        )?;
+
+        // the record stays in the vec until it is migrated, even at 0
+        let (_, user_balance, _) = &mut self.legacy_whitelist.address[pos];
+        *user_balance = user_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::SubtractionAtUpdateUserUnderflow)?;
+
+        Ok(())
+    }
+}
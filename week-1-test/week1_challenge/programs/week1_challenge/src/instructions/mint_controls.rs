@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::{thaw_account, ThawAccount},
+    token_interface::{
+        interest_bearing_mint_update_rate, InterestBearingMintUpdateRate, Mint, TokenAccount,
+        TokenInterface,
+    },
+};
+
+use crate::{error::VaultError, Vault, VAULT_SEED};
+
+// the vault PDA holds the rate and freeze authorities, the owner drives them through here
+#[derive(Accounts)]
+pub struct SetInterestRate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        has_one = owner @VaultError::NotAdmin,
+        has_one = mint @VaultError::WrongMint,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SetInterestRate<'info> {
+    pub fn set_interest_rate(&mut self, rate: i16) -> Result<()> {
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        interest_bearing_mint_update_rate(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                InterestBearingMintUpdateRate {
+                    token_program_id: self.token_program.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    rate_authority: self.vault_state.to_account_info(),
+                },
+                &[seeds],
+            ),
+            rate,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct ThawHolder<'info> {
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        has_one = owner @VaultError::NotAdmin,
+        has_one = mint @VaultError::WrongMint,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub holder_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ThawHolder<'info> {
+    /// Thaws an account created frozen under default_frozen
+    pub fn thaw_holder(&mut self) -> Result<()> {
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        thaw_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            ThawAccount {
+                account: self.holder_ata.to_account_info(),
+                mint: self.mint.to_account_info(),
+                authority: self.vault_state.to_account_info(),
+            },
+            &[seeds],
+        ))
+    }
+}
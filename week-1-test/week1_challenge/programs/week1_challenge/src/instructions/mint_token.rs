@@ -1,61 +1,109 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_2022::{mint_to_checked, MintToChecked},
+    token_2022::{mint_to_checked, thaw_account, MintToChecked, ThawAccount},
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::{error::VaultError, state::Whitelist};
+use crate::{error::VaultError, Vault, WhitelistEntry, VAULT_SEED, WHITELIST_ENTRY_SEED};
 
 #[derive(Accounts)]
 pub struct TokenFactory<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump,
+        has_one = owner @VaultError::NotAdmin,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
     #[account(
         mut,
-        mint::decimals = 9,
-        mint::authority = user,
-        extensions::transfer_hook::authority = user,
-        extensions::transfer_hook::program_id = hook_program_id.key(),
+        mint::authority = vault_state,
     )]
     pub mint: InterfaceAccount<'info, Mint>,
 
+    /// CHECK: wallet receiving the tokens, its entry proves it is whitelisted
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_entry.bump,
+        constraint = recipient_entry.is_whitelisted @VaultError::RecipientNotWhitelisted,
+    )]
+    pub recipient_entry: Account<'info, WhitelistEntry>,
+
     #[account(
         init_if_needed,
-        payer = user,
+        payer = owner,
         associated_token::mint = mint,
-        associated_token::authority = user,
+        associated_token::authority = recipient,
         associated_token::token_program = token_program,
     )]
-    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
-    /// CHECK: ExtraAccountMetaList Account, will be checked by the transfer hook
-    #[account(mut)]
-    pub extra_account_meta_list: UncheckedAccount<'info>,
-    #[account(
-        seeds = [b"whitelist"], 
-        bump
-    )]
-    pub blocklist: Account<'info, Whitelist>,
-    /// CHECK: Program id of the tf hook
-    pub hook_program_id: UncheckedAccount<'info>,
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl<'info> TokenFactory<'info> {
-    pub fn mint_to_admin(&mut self, amount: u64, decimals: u8) -> Result<()> {
-        let mint_ctx = CpiContext::new(
-            self.token_program.to_account_info(),
-            MintToChecked {
-                authority: self.user.to_account_info(),
-                mint: self.mint.to_account_info(),
-                to: self.source_token_account.to_account_info(),
-            },
-        );
-
-        mint_to_checked(mint_ctx, amount, decimals)?;
+    /// Mints `amount` to a whitelisted recipient within the vault's max supply and emission schedule
+    pub fn mint_to_recipient(&mut self, amount: u64) -> Result<()> {
+        let vault = &self.vault_state;
+
+        let new_supply = self
+            .mint
+            .supply
+            .checked_add(amount)
+            .ok_or(VaultError::MUltiplicationAtMint)?;
+        if let Some(max_supply) = vault.max_supply {
+            require!(new_supply <= max_supply, VaultError::MaxSupplyExceeded);
+        }
+        let total_minted = vault
+            .total_minted
+            .checked_add(amount)
+            .ok_or(VaultError::MUltiplicationAtMint)?;
+        if let Some(emission) = vault.emission {
+            let now = Clock::get()?.unix_timestamp;
+            require!(total_minted <= emission.released(now), VaultError::EmissionExceeded);
+        }
+
+        let bump_slice = &[vault.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        // on a default_frozen mint init_if_needed creates the ATA frozen, the recipient is whitelisted already
+        if self.recipient_ata.is_frozen() {
+            thaw_account(CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                ThawAccount {
+                    account: self.recipient_ata.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    authority: self.vault_state.to_account_info(),
+                },
+                &[seeds],
+            ))?;
+        }
+
+        mint_to_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                MintToChecked {
+                    authority: self.vault_state.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.recipient_ata.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            self.mint.decimals,
+        )?;
 
+        self.vault_state.total_minted = total_minted;
         Ok(())
     }
 }
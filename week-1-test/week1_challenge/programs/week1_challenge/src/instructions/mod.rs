@@ -10,5 +10,47 @@ pub use mint_token::*;
 pub mod deposit_withdraw;
 pub use deposit_withdraw::*;
 
+pub mod flash_loan;
+pub use flash_loan::*;
+
+pub mod reconcile;
+pub use reconcile::*;
+
+pub mod claim_whitelist;
+pub use claim_whitelist::*;
+
+pub mod prune_expired;
+pub use prune_expired::*;
+
+pub mod whitelist_batch;
+pub use whitelist_batch::*;
+
+pub mod migrate_vault;
+pub use migrate_vault::*;
+
+pub mod migrate_whitelist;
+pub use migrate_whitelist::*;
+
+pub mod request_claim_withdraw;
+pub use request_claim_withdraw::*;
+
+pub mod vault_config;
+pub use vault_config::*;
+
+pub mod admin_transfer;
+pub use admin_transfer::*;
+
+pub mod update_metadata;
+pub use update_metadata::*;
+
+pub mod transfer_fee;
+pub use transfer_fee::*;
+
+pub mod mint_controls;
+pub use mint_controls::*;
+
+pub mod credit_transfer;
+pub use credit_transfer::*;
+
 pub mod init_extra_accounts_meta;
 pub use init_extra_accounts_meta::*;
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{Mint, TokenInterface},
+};
+
+use crate::error::VaultError;
+use crate::instructions::holds_shares;
+use crate::{Vault, WhitelistEntry, SHARE_MINT_SEED, WHITELIST_ENTRY_SEED};
+
+#[derive(Accounts)]
+pub struct PruneExpired<'info> {
+    pub cranker: Signer<'info>,
+
+    /// CHECK: receives the rent, it paid for the entry
+    #[account(mut, address = vault.owner @VaultError::NotAdmin)]
+    pub owner: UncheckedAccount<'info>,
+
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = vault,
+        seeds = [WHITELIST_ENTRY_SEED, vault.key().as_ref(), whitelist_entry.owner.as_ref()],
+        bump = whitelist_entry.bump,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref()],
+        bump = vault.share_mint_bump,
+        mint::token_program = token_program,
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: the entry owner's share ATA, it may not exist if they never held shares
+    #[account(
+        address = get_associated_token_address_with_program_id(
+            &whitelist_entry.owner,
+            &share_mint.key(),
+            &token_program.key(),
+        ) @VaultError::WrongATA,
+    )]
+    pub owner_share_ata: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> PruneExpired<'info> {
+    pub fn prune_expired(&self) -> Result<()> {
+        let entry = &self.whitelist_entry;
+        require!(
+            entry.is_expired(Clock::get()?.unix_timestamp),
+            VaultError::EntryNotExpired
+        );
+        // an expired user with funds still has to be able to withdraw
+        require!(
+            entry.amount == 0 && entry.pending_shares == 0,
+            VaultError::EntryHasBalance
+        );
+        // shares received from others don't show up in the entry, their holder can still withdraw
+        require!(!holds_shares(&self.owner_share_ata)?, VaultError::EntryHasBalance);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::VaultError;
+use crate::{Reconciliation, Vault};
+
+#[derive(Accounts)]
+pub struct Reconcile<'info> {
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        has_one = mint @VaultError::WrongMint,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_state,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Reconcile<'info> {
+    /// Records total_assets plus requested withdrawals against the vault_ata balance.
+    /// Withheld transfer fees aren't part of the balance, so they don't count either way.
+    /// Legacy records are only booked by migrate_whitelist_chunk, until then they show as a surplus.
+    pub fn reconcile(&mut self, pause_on_mismatch: bool) -> Result<Reconciliation> {
+        let vault = &mut self.vault_state;
+        let authority = self.authority.key();
+        if pause_on_mismatch {
+            require!(
+                authority == vault.pauser || authority == vault.owner,
+                VaultError::NotPauser
+            );
+        }
+
+        let reconciliation = Reconciliation {
+            timestamp: Clock::get()?.unix_timestamp,
+            liabilities: vault
+                .total_assets
+                .checked_add(vault.pending_withdrawals)
+                .ok_or(VaultError::LiabilitiesOverflow)?,
+            holdings: self.vault_ata.amount,
+        };
+        msg!(
+            "Liabilities {} holdings {}",
+            reconciliation.liabilities,
+            reconciliation.holdings
+        );
+
+        if pause_on_mismatch && !reconciliation.is_balanced() {
+            vault.paused_withdrawals = true;
+        }
+        vault.last_reconciliation = Some(reconciliation);
+
+        Ok(reconciliation)
+    }
+}
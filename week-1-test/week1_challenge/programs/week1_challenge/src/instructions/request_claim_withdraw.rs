@@ -0,0 +1,238 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface},
+};
+use spl_token_2022::onchain;
+
+use crate::{
+    error::VaultError, program::Week1Challenge, Vault, Whitelist, WhitelistEntry,
+    WithdrawRequest, HOOK_AUTHORITY_SEED, SHARE_MINT_SEED, VAULT_SEED, WHITELIST_ENTRY_SEED,
+    WHITELIST_SEED, WITHDRAW_REQUEST_SEED,
+};
+
+#[derive(Accounts)]
+pub struct RequestWithdraw<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut @VaultError::VaultNotCreatedByAdmin,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), sender.key().as_ref()],
+        bump = whitelist_entry.bump,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
+    )]
+    pub user_share_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // one pending request per user, claim it before asking again
+    #[account(
+        init,
+        payer = sender,
+        seeds = [WITHDRAW_REQUEST_SEED, vault_state.key().as_ref(), sender.key().as_ref()],
+        space = WithdrawRequest::DISCRIMINATOR.len() + WithdrawRequest::INIT_SPACE,
+        bump,
+    )]
+    pub withdraw_request: Account<'info, WithdrawRequest>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RequestWithdraw<'info> {
+    /// Burns `shares` now and locks their current value until the cooldown passes
+    pub fn request_withdraw(&mut self, shares: u64, bumps: &RequestWithdrawBumps) -> Result<()> {
+        require!(!self.vault_state.paused_withdrawals, VaultError::WithdrawalsPaused);
+        require!(self.user_share_ata.amount >= shares, VaultError::InsufficientBalance);
+
+        let total_shares = self
+            .vault_state
+            .total_shares(self.share_mint.supply)
+            .ok_or(VaultError::ShareMathOverflow)?;
+        let amount = self
+            .vault_state
+            .assets_for_shares(shares, total_shares)
+            .ok_or(VaultError::ShareMathOverflow)?;
+
+        burn(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                Burn {
+                    mint: self.share_mint.to_account_info(),
+                    from: self.user_share_ata.to_account_info(),
+                    authority: self.sender.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        // the amount is owed to the user from now on, it no longer backs the remaining shares
+        let vault = &mut self.vault_state;
+        vault.total_assets = vault.total_assets.checked_sub(amount)
+            .ok_or(VaultError::SubtractionAtUpdateUserUnderflow)?;
+        vault.pending_withdrawals = vault.pending_withdrawals.checked_add(amount)
+            .ok_or(VaultError::AdditionAtUpdateUserOverflow)?;
+
+        let entry = &mut self.whitelist_entry;
+        entry.amount = entry.amount.saturating_sub(amount);
+
+        let unlock_at = Clock::get()?.unix_timestamp
+            .checked_add(self.vault_state.withdraw_cooldown)
+            .ok_or(VaultError::InvalidCooldown)?;
+
+        self.withdraw_request.set_inner(WithdrawRequest {
+            vault: self.vault_state.key(),
+            owner: self.sender.key(),
+            amount,
+            unlock_at,
+            bump: bumps.withdraw_request,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithdraw<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    #[account(
+        mut,
+        extensions::transfer_hook::program_id = hook_program_id.key(),
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Program id of the tf hook
+    pub hook_program_id: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    // the hook lists the vault and the source owner's entry as writable
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), vault_state.key().as_ref()],
+        bump = vault_entry.bump,
+    )]
+    pub vault_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        bump = whitelist.whitelist_bump,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        mut,
+        close = sender,
+        seeds = [WITHDRAW_REQUEST_SEED, vault_state.key().as_ref(), sender.key().as_ref()],
+        bump = withdraw_request.bump,
+    )]
+    pub withdraw_request: Account<'info, WithdrawRequest>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_state,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
+    )]
+    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Account containing the extra account
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub vault_program: Program<'info, Week1Challenge>,
+
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+
+    /// CHECK: signer of the hook's credit_transfer CPI, only passed through
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED],
+        seeds::program = hook_program_id.key(),
+        bump,
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> ClaimWithdraw<'info> {
+    pub fn claim_withdraw(&mut self) -> Result<()> {
+        require!(!self.vault_state.paused_withdrawals, VaultError::WithdrawalsPaused);
+        require!(
+            Clock::get()?.unix_timestamp >= self.withdraw_request.unlock_at,
+            VaultError::WithdrawalLocked
+        );
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        onchain::invoke_transfer_checked(
+            &self.token_program.key(),
+            self.vault_ata.to_account_info(),
+            self.mint.to_account_info(),
+            self.user_ata.to_account_info(),
+            self.vault_state.to_account_info(),
+            &[
+                self.extra_account_meta_list.to_account_info(),
+                self.hook_program_id.to_account_info(),
+                self.vault_program.to_account_info(),
+                self.vault_state.to_account_info(),
+                self.vault_entry.to_account_info(),
+                self.whitelist.to_account_info(),
+                self.share_mint.to_account_info(),
+                self.hook_authority.to_account_info(),
+            ],
+            self.withdraw_request.amount,
+            self.mint.decimals,
+            &[seeds],
+        )?;
+        let vault = &mut self.vault_state;
+        vault.pending_withdrawals = vault.pending_withdrawals.checked_sub(self.withdraw_request.amount)
+            .ok_or(VaultError::SubtractionAtUpdateUserUnderflow)?;
+        msg!("Claimed {} after cooldown", self.withdraw_request.amount);
+
+        Ok(())
+    }
+}
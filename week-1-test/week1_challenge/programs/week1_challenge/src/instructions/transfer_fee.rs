@@ -0,0 +1,247 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::{thaw_account, ThawAccount},
+    token_interface::{
+        transfer_fee_set, withdraw_withheld_tokens_from_accounts,
+        withdraw_withheld_tokens_from_mint, Mint, TokenAccount, TokenInterface,
+        TransferFeeSetTransferFee, WithdrawWithheldTokensFromAccounts,
+        WithdrawWithheldTokensFromMint,
+    },
+};
+use spl_token_2022::onchain;
+
+use crate::{
+    error::VaultError, program::Week1Challenge, Vault, Whitelist, WhitelistEntry,
+    HOOK_AUTHORITY_SEED, SHARE_MINT_SEED, TREASURY_SEED, VAULT_SEED, WHITELIST_ENTRY_SEED,
+    WHITELIST_SEED,
+};
+
+// the vault PDA is both the fee config and the withdraw withheld authority of the mint
+#[derive(Accounts)]
+pub struct HarvestFees<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    // owned by the vault so it stays put on admin rotation, withdraw_treasury pays it out
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [TREASURY_SEED, vault_state.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_state,
+        token::token_program = token_program,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> HarvestFees<'info> {
+    /// Sweeps the fees withheld on the token accounts in `remaining_accounts`
+    /// and on the mint itself into the treasury. Anyone can crank it.
+    pub fn harvest_fees(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        // a default_frozen mint creates the treasury frozen
+        if self.treasury.is_frozen() {
+            thaw_account(CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                ThawAccount {
+                    account: self.treasury.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    authority: self.vault_state.to_account_info(),
+                },
+                &[seeds],
+            ))?;
+        }
+
+        if !remaining_accounts.is_empty() {
+            withdraw_withheld_tokens_from_accounts(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    WithdrawWithheldTokensFromAccounts {
+                        token_program_id: self.token_program.to_account_info(),
+                        mint: self.mint.to_account_info(),
+                        destination: self.treasury.to_account_info(),
+                        authority: self.vault_state.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                remaining_accounts.to_vec(),
+            )?;
+        }
+
+        // fees harvested to the mint when accounts were closed
+        withdraw_withheld_tokens_from_mint(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            WithdrawWithheldTokensFromMint {
+                token_program_id: self.token_program.to_account_info(),
+                mint: self.mint.to_account_info(),
+                destination: self.treasury.to_account_info(),
+                authority: self.vault_state.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        self.treasury.reload()?;
+        msg!("Treasury balance after harvest: {}", self.treasury.amount);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        extensions::transfer_hook::program_id = hook_program_id.key(),
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Program id of the tf hook
+    pub hook_program_id: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [mint.key().as_ref(), VAULT_SEED],
+        bump = vault_state.vault_bump,
+        has_one = owner @VaultError::NotAdmin,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED, vault_state.key().as_ref()],
+        bump,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    // the hook checks the source owner's entry, the vault's here
+    #[account(
+        mut,
+        seeds = [WHITELIST_ENTRY_SEED, vault_state.key().as_ref(), vault_state.key().as_ref()],
+        bump = vault_entry.bump,
+    )]
+    pub vault_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        bump = whitelist.whitelist_bump,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault_state.key().as_ref()],
+        bump = vault_state.share_mint_bump,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Account containing the extra account
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub vault_program: Program<'info, Week1Challenge>,
+
+
+    /// CHECK: signer of the hook's credit_transfer CPI, only passed through
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED],
+        seeds::program = hook_program_id.key(),
+        bump,
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> WithdrawTreasury<'info> {
+    /// Pays `amount` of harvested fees out of the treasury to wherever the owner points
+    pub fn withdraw_treasury(&mut self, amount: u64) -> Result<()> {
+        require!(self.treasury.amount >= amount, VaultError::InsufficientBalance);
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        onchain::invoke_transfer_checked(
+            &self.token_program.key(),
+            self.treasury.to_account_info(),
+            self.mint.to_account_info(),
+            self.destination.to_account_info(),
+            self.vault_state.to_account_info(),
+            &[
+                self.extra_account_meta_list.to_account_info(),
+                self.hook_program_id.to_account_info(),
+                self.vault_program.to_account_info(),
+                self.vault_state.to_account_info(),
+                self.vault_entry.to_account_info(),
+                self.whitelist.to_account_info(),
+                self.share_mint.to_account_info(),
+                self.hook_authority.to_account_info(),
+            ],
+            amount,
+            self.mint.decimals,
+            &[seeds],
+        )?;
+        msg!("Withdrew {} from the treasury", amount);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetTransferFee<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        has_one = owner @VaultError::NotAdmin,
+        has_one = mint @VaultError::WrongMint,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SetTransferFee<'info> {
+    // Token-2022 applies the new fee two epochs later
+    pub fn set_transfer_fee(&mut self, basis_points: u16, maximum_fee: u64) -> Result<()> {
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        transfer_fee_set(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferFeeSetTransferFee {
+                    token_program_id: self.token_program.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    authority: self.vault_state.to_account_info(),
+                },
+                &[seeds],
+            ),
+            basis_points,
+            maximum_fee,
+        )
+    }
+}
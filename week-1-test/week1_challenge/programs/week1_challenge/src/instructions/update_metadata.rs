@@ -0,0 +1,95 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensions, StateWithExtensions},
+        state::Mint as SplMint,
+    },
+    token_interface::{
+        spl_token_metadata_interface::state::{Field, TokenMetadata},
+        token_metadata_update_field, Mint, TokenInterface, TokenMetadataUpdateField,
+    },
+};
+
+use crate::{error::VaultError, Vault, VAULT_SEED};
+
+/// "name", "symbol" and "uri" are the base fields, anything else is an additional field
+pub fn metadata_field(field: &str) -> Field {
+    match field {
+        "name" => Field::Name,
+        "symbol" => Field::Symbol,
+        "uri" => Field::Uri,
+        key => Field::Key(key.to_string()),
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    // the vault is the metadata update authority so it survives admin rotation
+    #[account(
+        has_one = owner @VaultError::NotAdmin,
+        has_one = mint @VaultError::WrongMint,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> UpdateMetadata<'info> {
+    pub fn update_metadata(&mut self, field: String, value: String) -> Result<()> {
+        let field = metadata_field(&field);
+        let mint_info = self.mint.to_account_info();
+
+        // token-2022 reallocs the mint itself but the rent has to be there already
+        let new_len = {
+            let data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<SplMint>::unpack(&data)?;
+            let mut metadata = mint
+                .get_variable_len_extension::<TokenMetadata>()
+                .map_err(|_| VaultError::MissingMetadata)?;
+            let old_size = metadata.tlv_size_of()?;
+            metadata.update(field.clone(), value.clone());
+            (data.len() + metadata.tlv_size_of()?).saturating_sub(old_size)
+        };
+        let rent_required = Rent::get()?.minimum_balance(new_len);
+        if rent_required > mint_info.lamports() {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.owner.to_account_info(),
+                        to: mint_info.clone(),
+                    },
+                ),
+                rent_required - mint_info.lamports(),
+            )?;
+        }
+
+        let bump_slice = &[self.vault_state.vault_bump];
+        let mint = self.mint.key();
+        let seeds: &[&[u8]] = &[mint.as_ref(), VAULT_SEED, bump_slice];
+
+        token_metadata_update_field(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TokenMetadataUpdateField {
+                    program_id: self.token_program.to_account_info(),
+                    metadata: mint_info,
+                    update_authority: self.vault_state.to_account_info(),
+                },
+                &[seeds],
+            ),
+            field,
+            value,
+        )
+    }
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::VaultError, Vault, WhitelistEntry};
+
+// admin settings stored on the vault
+#[derive(Accounts)]
+pub struct VaultConfig<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @VaultError::NotAdmin,
+    )]
+    pub vault_state: Account<'info, Vault>,
+}
+
+impl<'info> VaultConfig<'info> {
+    pub fn set_withdraw_cooldown(&mut self, seconds: i64) -> Result<()> {
+        require!(seconds >= 0, VaultError::InvalidCooldown);
+        self.vault_state.withdraw_cooldown = seconds;
+        Ok(())
+    }
+
+    pub fn set_pauser(&mut self, pauser: Pubkey) -> Result<()> {
+        self.vault_state.pauser = pauser;
+        Ok(())
+    }
+
+    // clears the flags passed as true
+    pub fn unpause(&mut self, deposits: bool, withdrawals: bool, transfers: bool) -> Result<()> {
+        let vault = &mut self.vault_state;
+        vault.paused_deposits &= !deposits;
+        vault.paused_withdrawals &= !withdrawals;
+        vault.paused_transfers &= !transfers;
+        Ok(())
+    }
+
+    // None lifts the cap
+    pub fn set_caps(&mut self, tvl_cap: Option<u64>, user_cap: Option<u64>) -> Result<()> {
+        self.vault_state.tvl_cap = tvl_cap;
+        self.vault_state.user_cap = user_cap;
+        Ok(())
+    }
+
+    // rotating only affects future claims, entries already claimed stay until removed
+    pub fn set_whitelist_root(&mut self, root: Option<[u8; 32]>) -> Result<()> {
+        self.vault_state.whitelist_root = root;
+        Ok(())
+    }
+
+    pub fn set_flash_fee(&mut self, fee_bps: Option<u16>) -> Result<()> {
+        require!(fee_bps.unwrap_or(0) <= 10_000, VaultError::InvalidFlashFee);
+        self.vault_state.flash_fee_bps = fee_bps;
+        Ok(())
+    }
+}
+
+// per-user cap override, the entry has to belong to this vault
+#[derive(Accounts)]
+pub struct SetUserCap<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        has_one = owner @VaultError::NotAdmin,
+    )]
+    pub vault_state: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        constraint = whitelist_entry.vault == vault_state.key() @VaultError::WrongWhitelistEntry,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+}
+
+impl<'info> SetUserCap<'info> {
+    pub fn set_user_cap(&mut self, cap: Option<u64>) -> Result<()> {
+        self.whitelist_entry.cap = cap;
+        Ok(())
+    }
+}
+
+// the pauser and the owner can both stop the vault
+#[derive(Accounts)]
+pub struct Pause<'info> {
+    #[account(
+        constraint = authority.key() == vault_state.pauser
+            || authority.key() == vault_state.owner @VaultError::NotPauser
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub vault_state: Account<'info, Vault>,
+}
+
+impl<'info> Pause<'info> {
+    // sets the flags passed as true, never clears one
+    pub fn pause(&mut self, deposits: bool, withdrawals: bool, transfers: bool) -> Result<()> {
+        let vault = &mut self.vault_state;
+        vault.paused_deposits |= deposits;
+        vault.paused_withdrawals |= withdrawals;
+        vault.paused_transfers |= transfers;
+        Ok(())
+    }
+}
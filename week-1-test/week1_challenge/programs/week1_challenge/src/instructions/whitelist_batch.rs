@@ -0,0 +1,212 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{allocate, assign, transfer, Allocate, Assign, Transfer},
+};
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{Mint, TokenInterface},
+};
+
+use crate::error::VaultError;
+use crate::instructions::{check_expiry, holds_shares};
+use crate::{Vault, WhitelistEntry, MAX_WHITELIST_BATCH, SHARE_MINT_SEED, WHITELIST_ENTRY_SEED};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WhitelistBatchEntry {
+    pub address: Pubkey,
+    // written to WhitelistEntry.cap, None keeps the cap of an existing entry
+    // and leaves new ones on Vault.user_cap
+    pub cap: Option<u64>,
+    pub expires_at: Option<i64>,
+}
+
+// remaining accounts: the WhitelistEntry PDA of every address, in the same order.
+// remove_many follows them with the share ATA of every address, in the same order
+#[derive(Accounts)]
+pub struct WhitelistBatch<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == vault.owner.key() @VaultError::NotAdmin
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(mut @VaultError::VaultNotCreatedByAdmin)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref()],
+        bump = vault.share_mint_bump,
+        mint::token_program = token_program,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> WhitelistBatch<'info> {
+    fn check_batch(len: usize, entries: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            len > 0 && len <= MAX_WHITELIST_BATCH as usize,
+            VaultError::InvalidBatchSize
+        );
+        require!(entries.len() == len, VaultError::BatchAccountsMismatch);
+        for (i, entry) in entries.iter().enumerate() {
+            require!(
+                entry.is_writable && entries[..i].iter().all(|other| other.key != entry.key),
+                VaultError::BatchAccountsMismatch
+            );
+        }
+        Ok(())
+    }
+
+    fn load_entry(&self, entry_info: &AccountInfo<'info>, owner: &Pubkey) -> Result<WhitelistEntry> {
+        let entry = WhitelistEntry::try_deserialize(&mut &entry_info.data.borrow()[..])?;
+        require!(
+            entry.vault == self.vault.key() && entry.owner == *owner,
+            VaultError::WrongWhitelistEntry
+        );
+        Ok(entry)
+    }
+
+    /// Whitelists every address, creating the entries that don't exist yet.
+    /// The rent for all new entries is taken from the admin in a single transfer.
+    pub fn add_many(
+        &mut self,
+        users: Vec<WhitelistBatchEntry>,
+        entries: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        Self::check_batch(users.len(), entries)?;
+        for user in &users {
+            check_expiry(user.expires_at)?;
+        }
+
+        let vault_key = self.vault.key();
+        let space = WhitelistEntry::DISCRIMINATOR.len() + WhitelistEntry::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(space);
+
+        // bumps of the entries to create, and what they still need to be rent exempt
+        let mut new_bumps = Vec::with_capacity(users.len());
+        let mut rent_due: u64 = 0;
+        for (user, entry_info) in users.iter().zip(entries) {
+            if entry_info.owner == &crate::ID {
+                new_bumps.push(None);
+                continue;
+            }
+            let (expected_entry, bump) = Pubkey::find_program_address(
+                &[WHITELIST_ENTRY_SEED, vault_key.as_ref(), user.address.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(entry_info.key(), expected_entry, VaultError::WrongWhitelistEntry);
+            rent_due += rent.saturating_sub(entry_info.lamports());
+            new_bumps.push(Some(bump));
+        }
+
+        // the vault holds the rent for a moment and hands it out to the new entries
+        if rent_due > 0 {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.admin.to_account_info(),
+                        to: self.vault.to_account_info(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+
+        for ((user, entry_info), bump) in users.into_iter().zip(entries).zip(new_bumps) {
+            let entry = match bump {
+                Some(bump) => {
+                    let top_up = rent.saturating_sub(entry_info.lamports());
+                    **self.vault.to_account_info().try_borrow_mut_lamports()? -= top_up;
+                    **entry_info.try_borrow_mut_lamports()? += top_up;
+
+                    let signer_seeds: &[&[&[u8]]] = &[&[
+                        WHITELIST_ENTRY_SEED,
+                        vault_key.as_ref(),
+                        user.address.as_ref(),
+                        &[bump],
+                    ]];
+                    allocate(
+                        CpiContext::new_with_signer(
+                            self.system_program.to_account_info(),
+                            Allocate { account_to_allocate: entry_info.clone() },
+                            signer_seeds,
+                        ),
+                        space as u64,
+                    )?;
+                    assign(
+                        CpiContext::new_with_signer(
+                            self.system_program.to_account_info(),
+                            Assign { account_to_assign: entry_info.clone() },
+                            signer_seeds,
+                        ),
+                        &crate::ID,
+                    )?;
+
+                    WhitelistEntry {
+                        vault: vault_key,
+                        owner: user.address,
+                        amount: 0,
+                        is_whitelisted: true,
+                        bump,
+                        cap: user.cap,
+                        pending_shares: 0,
+                        expires_at: user.expires_at,
+                    }
+                }
+                None => {
+                    let mut entry = self.load_entry(entry_info, &user.address)?;
+                    entry.is_whitelisted = true;
+                    if user.cap.is_some() {
+                        entry.cap = user.cap;
+                    }
+                    entry.expires_at = user.expires_at;
+                    entry
+                }
+            };
+            entry.try_serialize(&mut &mut entry_info.data.borrow_mut()[..])?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes every address. Entries whose owner still has a balance or shares are only disabled,
+    /// the rent of the closed ones goes back to the admin at once.
+    pub fn remove_many(&mut self, addresses: Vec<Pubkey>, accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let (entries, share_atas) = accounts.split_at(addresses.len().min(accounts.len()));
+        Self::check_batch(addresses.len(), entries)?;
+        require!(share_atas.len() == addresses.len(), VaultError::BatchAccountsMismatch);
+
+        let vault_key = self.vault.key();
+        let share_mint = self.share_mint.key();
+        let token_program = self.token_program.key();
+        let mut refund: u64 = 0;
+        for ((address, entry_info), share_ata) in addresses.iter().zip(entries).zip(share_atas) {
+            // every deposit and withdrawal moves tokens out of the vault's own entry
+            require_keys_neq!(*address, vault_key, VaultError::VaultEntryRequired);
+            require!(entry_info.owner == &crate::ID, VaultError::UserNotExistInVec);
+            require_keys_eq!(
+                share_ata.key(),
+                get_associated_token_address_with_program_id(address, &share_mint, &token_program),
+                VaultError::WrongATA
+            );
+            let mut entry = self.load_entry(entry_info, address)?;
+
+            if entry.amount > 0 || entry.pending_shares > 0 || holds_shares(share_ata)? {
+                entry.is_whitelisted = false;
+                entry.try_serialize(&mut &mut entry_info.data.borrow_mut()[..])?;
+            } else {
+                refund += entry_info.lamports();
+                **entry_info.try_borrow_mut_lamports()? = 0;
+                entry_info.assign(&System::id());
+                entry_info.resize(0)?;
+            }
+        }
+        **self.admin.to_account_info().try_borrow_mut_lamports()? += refund;
+
+        Ok(())
+    }
+}
@@ -1,11 +1,34 @@
 use anchor_lang::{prelude::*, system_program};
-use anchor_spl::token_interface::spl_pod::option::Nullable;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{spl_pod::option::Nullable, Mint, TokenAccount, TokenInterface},
+};
 
-use crate::{state::whitelist::Whitelist, Vault};
+use crate::{
+    state::whitelist::Whitelist, Vault, WhitelistEntry, SHARE_MINT_SEED, WHITELIST_ENTRY_SEED,
+    WHITELIST_SEED,
+};
 
 use crate::error::VaultError;
 use crate::instructions::VAULT_SEED;
 
+// an approval that has already lapsed is a mistake, not a removal
+pub fn check_expiry(expires_at: Option<i64>) -> Result<()> {
+    if let Some(expires_at) = expires_at {
+        require!(expires_at > Clock::get()?.unix_timestamp, VaultError::InvalidExpiry);
+    }
+    Ok(())
+}
+
+// shares reach a wallet by transfer without its entry seeing them, a missing ATA holds none
+pub fn holds_shares(share_ata: &AccountInfo) -> Result<bool> {
+    if share_ata.data_is_empty() {
+        return Ok(false);
+    }
+    let share_ata = TokenAccount::try_deserialize(&mut &share_ata.try_borrow_data()?[..])?;
+    Ok(share_ata.amount > 0)
+}
+
 #[derive(Accounts)]
 #[instruction(address: Pubkey, mint: Pubkey)]
 pub struct WhitelistOperations<'info> {
@@ -17,23 +40,59 @@ pub struct WhitelistOperations<'info> {
     #[account(
         init_if_needed,
         payer = admin,
-        seeds = [b"whitelist"],
-        space = 8 + 4 + (1 * 32 + 8 + 1) + 1, // 8 bytes for discriminator, 4 bytes for vector length, (1 * 32 + 8) => 1 length_of_vec * 32 pubkey_space + 8 u64_amount, 1 byte for bump  // but this is redundant since I'm allocating the size dynamically anyway, 8 + 4 + 1 would do anyway
+        seeds = [WHITELIST_SEED, mint.key().as_ref()],
+        space = Whitelist::space_for(0), // vaults created by create_vault already have one, this covers older vaults
         bump,
+        constraint = Pubkey::is_none(&whitelist.admin) || whitelist.admin == admin.key() @VaultError::NotAdmin
     )]
     pub whitelist: Account<'info, Whitelist>,
 
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [WHITELIST_ENTRY_SEED, vault.key().as_ref(), address.as_ref()],
+        space = WhitelistEntry::DISCRIMINATOR.len() + WhitelistEntry::INIT_SPACE,
+        bump,
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
     #[account(
         mut @VaultError::VaultNotCreatedByAdmin,
         seeds = [mint.key().as_ref(), VAULT_SEED],
         bump = vault.vault_bump,        
     )]
     pub vault: Account<'info, Vault>,
+
+    // only read by remove_from_whitelist, share holders keep their entry
+    #[account(
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref()],
+        bump = vault.share_mint_bump,
+        mint::token_program = token_program,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: the address's share ATA, it may not exist
+    #[account(
+        address = get_associated_token_address_with_program_id(
+            &address,
+            &share_mint.key(),
+            &token_program.key(),
+        ) @VaultError::WrongATA,
+    )]
+    pub owner_share_ata: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> WhitelistOperations<'info> {
-    pub fn add_to_whitelist(&mut self, address: Pubkey, bumps: &WhitelistOperationsBumps) -> Result<()> {
+    pub fn add_to_whitelist(
+        &mut self,
+        address: Pubkey,
+        expires_at: Option<i64>,
+        bumps: &WhitelistOperationsBumps,
+    ) -> Result<()> {
+        check_expiry(expires_at)?;
 
         
         let whitelist_accounts = &mut self.whitelist;
@@ -44,28 +103,37 @@ impl<'info> WhitelistOperations<'info> {
             whitelist_accounts.whitelist_bump = bumps.whitelist;
         }
 
-        if !whitelist_accounts.contains_address(&address) {
-            self.realloc_whitelist(true)?;
-            self.whitelist.address.push((address, 0, true));
+        // a fresh entry, or one that was only disabled because it still held a balance
+        let entry = &mut self.whitelist_entry;
+        if Pubkey::is_none(&entry.owner) {
+            entry.vault = self.vault.key();
+            entry.owner = address;
+            entry.amount = 0;
+            entry.bump = bumps.whitelist_entry;
         }
+        entry.is_whitelisted = true;
+        // re-adding renews the approval
+        entry.expires_at = expires_at;
+
         Ok(())
     }
 
     pub fn remove_from_whitelist(&mut self, address: Pubkey) -> Result<()> {
+        // every deposit and withdrawal moves tokens out of the vault's own entry
+        require_keys_neq!(address, self.vault.key(), VaultError::VaultEntryRequired);
 
-        let whitelist_accounts = &mut self.whitelist;
+        // init_if_needed just created it if the owner is still empty
         require!(
-            whitelist_accounts.contains_address(&address),
+            self.whitelist_entry.owner == address,
             VaultError::UserNotExistInVec
         );
-        if let Some(pos) = self
-            .whitelist
-            .address
-            .iter()
-            .position(|(addr, _, _)| *addr == address)
-        {
-            self.whitelist.address.remove(pos);
-            self.realloc_whitelist(false)?;
+
+        // keep the record while the user still has funds in the vault so they stay withdrawable
+        let entry = &self.whitelist_entry;
+        if entry.amount > 0 || entry.pending_shares > 0 || holds_shares(&self.owner_share_ata)? {
+            self.whitelist_entry.is_whitelisted = false;
+        } else {
+            self.whitelist_entry.close(self.admin.to_account_info())?;
         }
         Ok(())
     }
@@ -1,6 +1,7 @@
 pub mod constants;
 pub mod error;
 pub mod instructions;
+pub mod merkle;
 pub mod state;
 mod tests;
 
@@ -16,20 +17,22 @@ declare_id!("AkTTSsoAmjbmDQTVzFmoEWJ5o2j78xGBtSVuB68irJiJ");
 pub mod week1_challenge {
     use super::*;
 
-    pub fn create_vault(ctx: Context<VaultOperation>) -> Result<()> {
-        ctx.accounts.create_vault(&ctx.bumps)
+    pub fn create_vault(ctx: Context<VaultOperation>, config: VaultMintConfig) -> Result<()> {
+        ctx.accounts.create_vault(config, &ctx.bumps)
     }
 
-    pub fn mint_token(ctx: Context<TokenFactory>, amount: u64, decimals: u8) -> Result<()> {
-        ctx.accounts.mint_to_admin(amount, decimals)
+    // the vault PDA is the mint authority, only whitelisted wallets receive
+    pub fn mint_token(ctx: Context<TokenFactory>, amount: u64) -> Result<()> {
+        ctx.accounts.mint_to_recipient(amount)
     }
 
     pub fn add_to_whitelist(
         ctx: Context<WhitelistOperations>,
         address: Pubkey,
         _mint: Pubkey,
+        expires_at: Option<i64>,
     ) -> Result<()> {
-        ctx.accounts.add_to_whitelist(address, &ctx.bumps)
+        ctx.accounts.add_to_whitelist(address, expires_at, &ctx.bumps)
     }
 
     pub fn remove_from_whitelist(
@@ -40,6 +43,57 @@ pub mod week1_challenge {
         ctx.accounts.remove_from_whitelist(address)
     }
 
+    // up to MAX_WHITELIST_BATCH addresses per call, remaining accounts are their entries in order
+    pub fn add_many<'info>(
+        ctx: Context<'_, '_, '_, 'info, WhitelistBatch<'info>>,
+        users: Vec<WhitelistBatchEntry>,
+    ) -> Result<()> {
+        ctx.accounts.add_many(users, ctx.remaining_accounts)
+    }
+
+    // remaining accounts are the entries followed by the share ATAs of the addresses, both in order
+    pub fn remove_many<'info>(
+        ctx: Context<'_, '_, '_, 'info, WhitelistBatch<'info>>,
+        addresses: Vec<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.remove_many(addresses, ctx.remaining_accounts)
+    }
+
+    // None turns Merkle mode off, existing entries are kept
+    pub fn set_whitelist_root(ctx: Context<VaultConfig>, root: Option<[u8; 32]>) -> Result<()> {
+        ctx.accounts.set_whitelist_root(root)
+    }
+
+    // creates the caller's entry if the proof places them under the vault's root
+    pub fn claim_whitelist(ctx: Context<ClaimWhitelist>, proof: Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.claim_whitelist(proof, &ctx.bumps)
+    }
+
+    // None disables flash loans
+    pub fn set_flash_fee(ctx: Context<VaultConfig>, fee_bps: Option<u16>) -> Result<()> {
+        ctx.accounts.set_flash_fee(fee_bps)
+    }
+
+    // lends from vault_ata, the same transaction has to call flash_repay afterwards
+    pub fn flash_borrow(ctx: Context<FlashLoan>, amount: u64) -> Result<()> {
+        ctx.accounts.flash_borrow(amount)
+    }
+
+    // returns the loan plus fee, the fee goes to depositors through total_assets
+    pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+        ctx.accounts.flash_repay()
+    }
+
+    // compares booked liabilities with vault_ata, only the pauser or owner may pause on a mismatch
+    pub fn reconcile(ctx: Context<Reconcile>, pause_on_mismatch: bool) -> Result<Reconciliation> {
+        ctx.accounts.reconcile(pause_on_mismatch)
+    }
+
+    // anyone can close an expired entry that holds nothing, the rent goes back to the vault owner
+    pub fn prune_expired(ctx: Context<PruneExpired>) -> Result<()> {
+        ctx.accounts.prune_expired()
+    }
+
     // deposit
     pub fn deposit<'info>(
         ctx: Context<'_, '_, '_, 'info, DepositWithdraw<'info>>,
@@ -47,13 +101,134 @@ pub mod week1_challenge {
     ) -> Result<()> {
         ctx.accounts.deposit(amount, &ctx.remaining_accounts)
     }
-    // withdraw
+    // withdraw, amount is in shares
     pub fn withdraw<'info>(
         ctx: Context<'_, '_, '_, 'info, DepositWithdraw<'info>>,
         amount: u64,
     ) -> Result<()> {
         ctx.accounts.withdraw(amount, &ctx.remaining_accounts)
     }
+
+    // rewrite a vault from an older program version to the current layout, owner only
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        ctx.accounts.migrate_vault(&ctx.bumps)
+    }
+
+    // move legacy whitelist records into per-user entries, [user_ata, whitelist_entry, user_share_ata] per record in remaining accounts
+    pub fn migrate_whitelist_chunk<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateWhitelist<'info>>,
+        _mint: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.migrate_whitelist_chunk(ctx.remaining_accounts)
+    }
+
+    // burn shares now, claim the tokens once the vault cooldown has passed
+    pub fn request_withdraw(ctx: Context<RequestWithdraw>, amount: u64) -> Result<()> {
+        ctx.accounts.request_withdraw(amount, &ctx.bumps)
+    }
+
+    pub fn claim_withdraw(ctx: Context<ClaimWithdraw>) -> Result<()> {
+        ctx.accounts.claim_withdraw()
+    }
+
+    pub fn set_withdraw_cooldown(ctx: Context<VaultConfig>, seconds: i64) -> Result<()> {
+        ctx.accounts.set_withdraw_cooldown(seconds)
+    }
+
+    pub fn set_caps(
+        ctx: Context<VaultConfig>,
+        tvl_cap: Option<u64>,
+        user_cap: Option<u64>,
+    ) -> Result<()> {
+        ctx.accounts.set_caps(tvl_cap, user_cap)
+    }
+
+    pub fn set_user_cap(ctx: Context<SetUserCap>, cap: Option<u64>) -> Result<()> {
+        ctx.accounts.set_user_cap(cap)
+    }
+
+    pub fn set_pauser(ctx: Context<VaultConfig>, pauser: Pubkey) -> Result<()> {
+        ctx.accounts.set_pauser(pauser)
+    }
+
+    pub fn pause(
+        ctx: Context<Pause>,
+        deposits: bool,
+        withdrawals: bool,
+        transfers: bool,
+    ) -> Result<()> {
+        ctx.accounts.pause(deposits, withdrawals, transfers)
+    }
+
+    pub fn unpause(
+        ctx: Context<VaultConfig>,
+        deposits: bool,
+        withdrawals: bool,
+        transfers: bool,
+    ) -> Result<()> {
+        ctx.accounts.unpause(deposits, withdrawals, transfers)
+    }
+
+    // two-step owner rotation, the mint authorities move with it
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.propose_admin(new_owner)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        ctx.accounts.accept_admin()
+    }
+
+    // drops a pending proposal, the authorities go back to the owner
+    pub fn cancel_admin(ctx: Context<ProposeAdmin>) -> Result<()> {
+        ctx.accounts.cancel_admin()
+    }
+
+    // set a base or additional metadata field on the vault mint, tops up rent when it grows
+    pub fn update_metadata(ctx: Context<UpdateMetadata>, field: String, value: String) -> Result<()> {
+        ctx.accounts.update_metadata(field, value)
+    }
+
+    // sweep withheld transfer fees from the given token accounts and the mint into the treasury
+    pub fn harvest_fees<'info>(ctx: Context<'_, '_, '_, 'info, HarvestFees<'info>>) -> Result<()> {
+        ctx.accounts.harvest_fees(ctx.remaining_accounts)
+    }
+
+    // pay harvested fees out of the vault's treasury, owner only
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_treasury(amount)
+    }
+
+    pub fn set_transfer_fee(
+        ctx: Context<SetTransferFee>,
+        basis_points: u16,
+        maximum_fee: u64,
+    ) -> Result<()> {
+        ctx.accounts.set_transfer_fee(basis_points, maximum_fee)
+    }
+
+    // the vault is the rate authority, only the owner can move it
+    pub fn set_interest_rate(ctx: Context<SetInterestRate>, rate: i16) -> Result<()> {
+        ctx.accounts.set_interest_rate(rate)
+    }
+
+    // thaw a holder's account on a default_frozen mint, the vault is the freeze authority
+    pub fn thaw_holder(ctx: Context<ThawHolder>) -> Result<()> {
+        ctx.accounts.thaw_holder()
+    }
+
+    // signed by the transfer hook when tokens reach vault_ata without going through deposit
+    pub fn credit_transfer(ctx: Context<CreditTransfer>, amount: u64) -> Result<()> {
+        ctx.accounts.credit_transfer(amount)
+    }
+
+    pub fn claim_shares(ctx: Context<ClaimShares>) -> Result<()> {
+        ctx.accounts.claim_shares()
+    }
+
+    // withdraw against a record that hasn't been migrated yet
+    pub fn withdraw_legacy(ctx: Context<LegacyWithdraw>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_legacy(amount)
+    }
 }
 
 // create_vault ->
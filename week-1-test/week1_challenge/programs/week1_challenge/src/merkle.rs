@@ -0,0 +1,101 @@
+//! Merkle allowlist shared by claim_whitelist and the off-chain tree builder.
+//!
+//! Leaves are `sha256(0x00 || address)` and nodes `sha256(0x01 || lo || hi)` with the
+//! children sorted, so a proof is just the list of siblings from the leaf up.
+//! An odd node at the end of a level is carried up unchanged.
+
+use std::str::FromStr;
+
+use anchor_lang::{prelude::Pubkey, solana_program::hash::hashv};
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf_hash(address: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, address.as_ref()]).to_bytes()
+}
+
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, lo, hi]).to_bytes()
+}
+
+pub fn verify_proof(root: &[u8; 32], address: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf_hash(address), |node, sibling| node_hash(&node, sibling));
+    computed == *root
+}
+
+/// Allowlist tree built off-chain, publish `root()` with set_whitelist_root
+/// and hand every user their `proof()` for claim_whitelist
+pub struct MerkleTree {
+    addresses: Vec<Pubkey>,
+    // levels[0] are the leaves in address order, the last level is the root
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn new(mut addresses: Vec<Pubkey>) -> Self {
+        addresses.sort();
+        addresses.dedup();
+
+        let mut levels = vec![addresses.iter().map(leaf_hash).collect::<Vec<_>>()];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => node_hash(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self { addresses, levels }
+    }
+
+    /// One address per line in the first column, blank lines, `#` comments
+    /// and a header row that isn't a pubkey are skipped
+    pub fn from_csv(csv: &str) -> Result<Self, String> {
+        let mut addresses = Vec::new();
+        for (i, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let field = line.split(',').next().unwrap_or_default().trim();
+            match Pubkey::from_str(field) {
+                Ok(address) => addresses.push(address),
+                Err(_) if i == 0 => continue,
+                Err(_) => return Err(format!("line {}: invalid address {:?}", i + 1, field)),
+            }
+        }
+        if addresses.is_empty() {
+            return Err("no addresses in csv".to_string());
+        }
+        Ok(Self::new(addresses))
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap()[0]
+    }
+
+    pub fn addresses(&self) -> &[Pubkey] {
+        &self.addresses
+    }
+
+    pub fn proof(&self, address: &Pubkey) -> Option<Vec<[u8; 32]>> {
+        let mut index = self.addresses.binary_search(address).ok()?;
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
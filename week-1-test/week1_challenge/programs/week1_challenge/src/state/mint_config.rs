@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::EmissionSchedule;
+
+/// Mint setup chosen at create_vault, the transfer hook is always on
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct VaultMintConfig {
+    pub decimals: u8,
+    pub transfer_fee: Option<TransferFeeArgs>,
+    pub metadata: Option<MetadataArgs>,
+    // basis points, the vault is the rate authority, see set_interest_rate
+    pub interest_rate: Option<i16>,
+    // new token accounts start frozen, the vault is the freeze authority, see thaw_holder
+    pub default_frozen: bool,
+    pub permanent_delegate: Option<Pubkey>,
+    // caps for mint_token, stored on the vault
+    pub max_supply: Option<u64>,
+    pub emission: Option<EmissionSchedule>,
+}
+
+impl Default for VaultMintConfig {
+    fn default() -> Self {
+        Self {
+            decimals: 9,
+            transfer_fee: None,
+            metadata: None,
+            interest_rate: None,
+            default_frozen: false,
+            permanent_delegate: None,
+            max_supply: None,
+            emission: None,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TransferFeeArgs {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MetadataArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub additional_fields: Vec<(String, String)>,
+}
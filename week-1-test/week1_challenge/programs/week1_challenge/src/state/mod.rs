@@ -3,3 +3,12 @@ pub use vault::*;
 
 pub mod whitelist;
 pub use whitelist::*;
+
+pub mod whitelist_entry;
+pub use whitelist_entry::*;
+
+pub mod withdraw_request;
+pub use withdraw_request::*;
+
+pub mod mint_config;
+pub use mint_config::*;
@@ -1,9 +1,164 @@
 use anchor_lang::prelude::*;
 
+use crate::{error::VaultError, WhitelistEntry};
+
 #[account]
 #[derive(InitSpace)]
 pub struct Vault {
     pub mint: Pubkey,
     pub vault_bump: u8,
     pub owner: Pubkey,
+    // receipt token minted on deposit, seeds = [SHARE_MINT_SEED, vault]
+    pub share_mint_bump: u8,
+    // assets the shares are backed by, excludes anything sent to vault_ata outside of deposit
+    pub total_assets: u64,
+    // taken out of total_assets by request_withdraw, still in vault_ata until claim_withdraw
+    pub pending_withdrawals: u64,
+    // seconds between request_withdraw and claim_withdraw, 0 keeps withdraw instant
+    pub withdraw_cooldown: i64,
+    // None is unlimited
+    pub tvl_cap: Option<u64>,
+    // per-user cap for entries without their own
+    pub user_cap: Option<u64>,
+    // can only pause, unpausing is left to the owner
+    pub pauser: Pubkey,
+    pub paused_deposits: bool,
+    pub paused_withdrawals: bool,
+    // hooked transfers between users, deposits and withdrawals have their own flags
+    pub paused_transfers: bool,
+    // proposed by the owner, becomes owner on accept_admin
+    pub pending_owner: Pubkey,
+    // the vault PDA is the mint authority, these bound what mint_token may issue
+    pub max_supply: Option<u64>,
+    pub emission: Option<EmissionSchedule>,
+    // everything mint_token has issued, burns don't give emission back
+    pub total_minted: u64,
+    // owed to users who deposited by plain transfer, count towards the share supply
+    pub pending_shares: u64,
+    // Merkle mode, users listed under the root claim their own entry with claim_whitelist
+    pub whitelist_root: Option<[u8; 32]>,
+    // result of the latest reconcile call
+    pub last_reconciliation: Option<Reconciliation>,
+    // None keeps flash loans off
+    pub flash_fee_bps: Option<u16>,
+    // lent by flash_borrow and not yet returned, only ever set within one transaction
+    pub flash_loan: Option<u64>,
+    // set while this program moves tokens into vault_ata itself, the hook leaves those to it
+    pub vault_transfer: bool,
+    // layout version, migrate_vault brings older vaults up to VAULT_VERSION
+    pub version: u8,
+}
+
+/// Layout of vaults created before the share mint and per-vault whitelist existed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
+pub struct VaultV0 {
+    pub mint: Pubkey,
+    pub vault_bump: u8,
+    pub owner: Pubkey,
+}
+
+/// Recorded liabilities against what vault_ata actually holds
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
+pub struct Reconciliation {
+    pub timestamp: i64,
+    pub liabilities: u64,
+    pub holdings: u64,
+}
+
+impl Reconciliation {
+    pub fn is_balanced(&self) -> bool {
+        self.liabilities == self.holdings
+    }
+}
+
+/// Linear release of mintable tokens starting at `start`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
+pub struct EmissionSchedule {
+    pub start: i64,
+    pub per_second: u64,
+}
+
+impl EmissionSchedule {
+    /// Total amount released for minting by `now`
+    pub fn released(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start).max(0) as u64;
+        self.per_second.saturating_mul(elapsed)
+    }
+}
+
+impl Vault {
+    /// Fee owed on a flash loan of `amount`, rounded up so small loans aren't free
+    pub fn flash_fee(&self, amount: u64) -> Option<u64> {
+        let bps = self.flash_fee_bps? as u128;
+        let fee = (amount as u128).checked_mul(bps)?.div_ceil(10_000);
+        u64::try_from(fee).ok()
+    }
+
+    /// Shares minted for depositing `assets`, 1:1 while the vault is empty. Rounds down.
+    pub fn shares_for_assets(&self, assets: u64, total_shares: u64) -> Option<u64> {
+        if total_shares == 0 || self.total_assets == 0 {
+            return Some(assets);
+        }
+        let shares = (assets as u128)
+            .checked_mul(total_shares as u128)?
+            .checked_div(self.total_assets as u128)?;
+        u64::try_from(shares).ok()
+    }
+
+    /// Share supply to price against, minted shares plus those still owed
+    pub fn total_shares(&self, minted: u64) -> Option<u64> {
+        minted.checked_add(self.pending_shares)
+    }
+
+    /// Books `received` assets for `entry` within the caps and returns the shares they are worth
+    pub fn book_deposit(
+        &mut self,
+        entry: &mut WhitelistEntry,
+        received: u64,
+        minted_shares: u64,
+    ) -> Result<u64> {
+        let new_total_assets = self
+            .total_assets
+            .checked_add(received)
+            .ok_or(VaultError::AdditionAtUpdateUserOverflow)?;
+        if let Some(tvl_cap) = self.tvl_cap {
+            require!(new_total_assets <= tvl_cap, VaultError::TvlCapExceeded);
+        }
+        let new_user_amount = entry
+            .amount
+            .checked_add(received)
+            .ok_or(VaultError::AdditionAtUpdateUserOverflow)?;
+        if let Some(user_cap) = self.cap_for(entry) {
+            require!(new_user_amount <= user_cap, VaultError::UserCapExceeded);
+        }
+
+        // price the shares before the deposit moves total_assets
+        let total_shares = self
+            .total_shares(minted_shares)
+            .ok_or(VaultError::ShareMathOverflow)?;
+        let shares = self
+            .shares_for_assets(received, total_shares)
+            .ok_or(VaultError::ShareMathOverflow)?;
+        require!(shares > 0, VaultError::ZeroShares);
+
+        self.total_assets = new_total_assets;
+        entry.amount = new_user_amount;
+        Ok(shares)
+    }
+
+    /// Cap for one user, the entry's own cap wins over the vault default
+    pub fn cap_for(&self, entry: &WhitelistEntry) -> Option<u64> {
+        entry.cap.or(self.user_cap)
+    }
+
+    /// Assets paid out for burning `shares`. Rounds down.
+    pub fn assets_for_shares(&self, shares: u64, total_shares: u64) -> Option<u64> {
+        if total_shares == 0 {
+            return None;
+        }
+        let assets = (shares as u128)
+            .checked_mul(self.total_assets as u128)?
+            .checked_div(total_shares as u128)?;
+        u64::try_from(assets).ok()
+    }
 }
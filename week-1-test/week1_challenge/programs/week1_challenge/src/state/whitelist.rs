@@ -4,12 +4,21 @@ use anchor_lang::prelude::*;
 #[account]
 // #[derive(InitSpace)] can't use initspace since i'm using a dynamic vec
 pub struct Whitelist {
+    // legacy per-user records, membership and balances now live in WhitelistEntry PDAs
     pub address: Vec<(Pubkey, u64, bool)>,
     pub whitelist_bump: u8,
     pub admin: Pubkey,
 }
 
 impl Whitelist {
+    // Pubkey + u64 + bool
+    pub const LEGACY_RECORD_SIZE: usize = 32 + 8 + 1;
+
+    /// account size needed to hold `records` legacy records, discriminator included
+    pub fn space_for(records: usize) -> usize {
+        8 + 4 + records * Self::LEGACY_RECORD_SIZE + 1 + 32
+    }
+
     pub fn contains_address(&self, address: &Pubkey) -> bool {
         self.address.iter().any(|(addr, _, _)| addr == address)
     }
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+/// Whitelist membership and balance of one user in one vault
+/// seeded by [WHITELIST_ENTRY_SEED, vault, owner] so lookups stay constant at any whitelist size
+#[account]
+#[derive(InitSpace)]
+pub struct WhitelistEntry {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub is_whitelisted: bool,
+    pub bump: u8,
+    // overrides Vault.user_cap when set
+    pub cap: Option<u64>,
+    // booked by the transfer hook for direct transfers, minted on the user's next vault call
+    pub pending_shares: u64,
+    // unix timestamp the approval lapses at, None never expires
+    pub expires_at: Option<i64>,
+}
+
+impl WhitelistEntry {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
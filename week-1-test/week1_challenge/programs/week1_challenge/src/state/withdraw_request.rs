@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Pending withdrawal of one user, seeded by [WITHDRAW_REQUEST_SEED, vault, owner]
+/// the shares are already burnt, `amount` is what claim_withdraw pays out
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawRequest {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+    pub bump: u8,
+}
@@ -6,16 +6,18 @@ mod tests {
             error::Error,
             prelude::{msg, AccountMeta},
             solana_program::{
-                hash::Hash, native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey,
+                clock::Clock, hash::Hash, native_token::LAMPORTS_PER_SOL, program_pack::Pack,
+                pubkey::Pubkey,
             },
             system_program::ID as SYSTEM_PROGRAM_ID,
-            AccountDeserialize, InstructionData, Key, ToAccountMetas,
+            AccountDeserialize, AccountSerialize, InstructionData, Key, ToAccountMetas,
         },
         anchor_spl::{
             associated_token::{self, spl_associated_token_account},
             token_2022::spl_token_2022,
         },
         litesvm::LiteSVM,
+        solana_account::Account,
         solana_address::Address,
         solana_hash::Hash as SolanaHash,
         solana_instruction::Instruction,
@@ -51,6 +53,7 @@ mod tests {
     // }
 
     use crate::ID as PROGRAM_ID;
+    use anchor_lang::solana_program::sysvar::instructions::ID as INSTRUCTIONS_SYSVAR_ID;
     const TRANSFER_HOOK_PROGRAM_ID: Pubkey = transfer_hook::ID;
 
     pub struct ReusableData {
@@ -59,6 +62,9 @@ mod tests {
         system_program: Pubkey,
         vault_ata: Pubkey,
         vault_state: Pubkey,
+        vault_entry: Pubkey,
+        whitelist: Pubkey,
+        share_mint: Pubkey,
         mint: Keypair,
         admin: Keypair,
     }
@@ -69,10 +75,6 @@ mod tests {
 
     pub fn setup() -> (LiteSVM, ReusableData) {
         let mut svm = LiteSVM::new();
-        let admin = Keypair::new();
-
-        svm.airdrop(&admin.pubkey(), 10 * LAMPORTS_PER_SOL)
-            .expect("Failed to airdrop SOL to payer");
 
         // Load program SO file
         let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -87,11 +89,6 @@ mod tests {
 
         let program_id = PROGRAM_ID;
         let hook_program_id = TRANSFER_HOOK_PROGRAM_ID;
-        let spl_program_id = spl_token_2022::ID;
-        let ata_program_id = spl_associated_token_account::ID;
-        let system_program_id = SYSTEM_PROGRAM_ID;
-
-        let mint = Keypair::new();
 
         svm.add_program(hook_program_id, &hook_program_data)
             .expect("failed to add hook program");
@@ -99,9 +96,50 @@ mod tests {
         svm.add_program(program_id, &program_data)
             .expect("failed to add vault program");
 
+        let exported_state = vault_data(&mut svm);
+
+        (svm, exported_state)
+    }
+
+    // fresh admin and mint keys for one more vault in the same svm
+    pub fn vault_data(svm: &mut LiteSVM) -> ReusableData {
+        let admin = Keypair::new();
+
+        svm.airdrop(&admin.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL to payer");
+
+        let program_id = PROGRAM_ID;
+        let spl_program_id = spl_token_2022::ID;
+        let ata_program_id = spl_associated_token_account::ID;
+        let system_program_id = SYSTEM_PROGRAM_ID;
+
+        let mint = Keypair::new();
+
         let vault_state =
             Pubkey::find_program_address(&[&mint.pubkey().to_bytes(), b"vault"], &program_id).0;
 
+        let vault_entry = Pubkey::find_program_address(
+            &[
+                crate::WHITELIST_ENTRY_SEED,
+                vault_state.as_ref(),
+                vault_state.as_ref(),
+            ],
+            &program_id,
+        )
+        .0;
+
+        let whitelist = Pubkey::find_program_address(
+            &[crate::WHITELIST_SEED, mint.pubkey().as_ref()],
+            &program_id,
+        )
+        .0;
+
+        let share_mint = Pubkey::find_program_address(
+            &[crate::SHARE_MINT_SEED, vault_state.as_ref()],
+            &program_id,
+        )
+        .0;
+
         let vault_ata = associated_token::get_associated_token_address_with_program_id(
             &vault_state,
             &mint.pubkey(),
@@ -116,9 +154,12 @@ mod tests {
             mint,
             vault_ata: vault_ata,
             vault_state,
+            vault_entry,
+            whitelist,
+            share_mint,
         };
 
-        (svm, exported_state)
+        exported_state
     }
 
     #[test]
@@ -139,9 +180,15 @@ mod tests {
                 token_program: reusable_data.token_program,
                 vault_ata: reusable_data.vault_ata,
                 vault_state: reusable_data.vault_state,
+                vault_entry: reusable_data.vault_entry,
+                whitelist: reusable_data.whitelist,
+                share_mint: reusable_data.share_mint,
             }
             .to_account_metas(None),
-            data: crate::instruction::CreateVault {}.data(),
+            data: crate::instruction::CreateVault {
+                config: crate::state::VaultMintConfig::default(),
+            }
+            .data(),
         };
 
         let recent_blockhash = svm.latest_blockhash();
@@ -215,7 +262,7 @@ mod tests {
         msg!("MetaList state: {:?}", new_state_of_metalist.data);
 
         // 🔥🔥🔥🔥 [3] add to whitelist
-        let whitelist = Pubkey::find_program_address(&[b"whitelist"], &PROGRAM_ID.key()).0;
+        let whitelist = reusable_data.whitelist;
 
         let new_user = Keypair::new();
 
@@ -228,18 +275,33 @@ mod tests {
             &reusable_data.token_program.key(),
         );
 
-        let add_to_whitelist_ix = Instruction {
+        let whitelist_entry = Pubkey::find_program_address(
+            &[
+                crate::WHITELIST_ENTRY_SEED,
+                reusable_data.vault_state.as_ref(),
+                new_user.pubkey().as_ref(),
+            ],
+            &PROGRAM_ID,
+        )
+        .0;
+
+        let whitelist_new_user_ix = Instruction {
             program_id: PROGRAM_ID,
             accounts: crate::accounts::WhitelistOperations {
                 whitelist: whitelist.key(),
+                whitelist_entry,
                 vault: reusable_data.vault_state.key(),
+                share_mint: reusable_data.share_mint,
+                owner_share_ata: share_ata_of(&reusable_data, &new_user.pubkey()),
                 admin: reusable_data.admin.pubkey(),
                 system_program: reusable_data.system_program.key(),
+                token_program: reusable_data.token_program,
             }
             .to_account_metas(None),
             data: crate::instruction::AddToWhitelist {
-                address: new_user_ata.key(),
+                address: new_user.pubkey(),
                 _mint: reusable_data.mint.pubkey(),
+                expires_at: None,
             }
             .data(),
         };
@@ -247,7 +309,7 @@ mod tests {
         let recent_blockhash = svm.latest_blockhash();
 
         let transaction3 = Transaction::new_signed_with_payer(
-            &[add_to_whitelist_ix],
+            &[whitelist_new_user_ix],
             Some(&reusable_data.admin.pubkey()),
             &[&reusable_data.admin],
             recent_blockhash,
@@ -255,20 +317,20 @@ mod tests {
 
         svm.send_transaction(transaction3).unwrap();
 
-        let whitelist_account_info = svm.get_account(&whitelist).unwrap();
-        let mut whitelist_data_slice: &[u8] = whitelist_account_info.data.as_ref();
-
-        let fetched_whitelist_state =
-            crate::state::Whitelist::try_deserialize(&mut whitelist_data_slice)
-                .expect("Failed to deserialize Whitelist account data. Check initialization.");
+        let whitelist_entry_account = svm.get_account(&whitelist_entry).unwrap();
+        let fetched_entry =
+            crate::state::WhitelistEntry::try_deserialize(&mut whitelist_entry_account.data.as_ref())
+                .expect("Failed to deserialize WhitelistEntry account data. Check initialization.");
 
-        // 4. Use the deserialized struct to check the address.
-        let contains_address = fetched_whitelist_state.contains_address(&new_user_ata.key());
+        // 4. Use the deserialized entry to check the address.
+        let contains_address =
+            fetched_entry.owner == new_user.pubkey() && fetched_entry.is_whitelisted;
 
         assert!(
             contains_address,
             "You were not successfully added to the whitelist"
         );
+        assert_eq!(fetched_entry.vault, reusable_data.vault_state);
         msg!("contains you: {}", contains_address);
 
         msg!("🔥🔥🔥 [4] mint token to self");
@@ -284,31 +346,22 @@ mod tests {
         //  svm.airdrop(&admin.pubkey(), 10 * LAMPORTS_PER_SOL)
         //     .expect("Failed to airdrop SOL to payer");
 
-        let mint_token_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::TokenFactory {
-                extra_account_meta_list,
-                mint: reusable_data.mint.pubkey(),
-                associated_token_program: reusable_data.ata_program.key(),
-                blocklist: whitelist,
-                hook_program_id: TRANSFER_HOOK_PROGRAM_ID.key(),
-                source_token_account: admin_ata.key(),
-                system_program: reusable_data.system_program.key(),
-                token_program: reusable_data.token_program.key(),
-                user: reusable_data.admin.pubkey(),
-            }
-            .to_account_metas(None),
-            data: crate::instruction::MintToken {
-                amount: 10_000,
-                decimals: 9,
-            }
-            .data(),
-        };
+        // only whitelisted wallets can receive, the admin included
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(
+                &reusable_data,
+                &reusable_data.admin.pubkey(),
+                &reusable_data.admin.pubkey(),
+            )],
+            &[&reusable_data.admin],
+        );
+        let mint_admin_ix = mint_token_ix(&reusable_data, &reusable_data.admin.pubkey(), 10_000);
 
         let recent_blockhash = svm.latest_blockhash();
 
         let transaction4 = Transaction::new_signed_with_payer(
-            &[mint_token_ix],
+            &[mint_admin_ix],
             Some(&reusable_data.admin.pubkey()),
             &[&reusable_data.admin],
             recent_blockhash,
@@ -339,16 +392,16 @@ mod tests {
             .expect("Slice to array conversion failed");
 
         // Convert the bytes to a u64 (Solana uses little-endian)
-        let token_balance = u64::from_le_bytes(amount_bytes);
+        let admin_balance = u64::from_le_bytes(amount_bytes);
 
         // --- Verification ---
 
         // You are now successfully reading the balance directly.
-        msg!("Admin ATA token balance (POD Read): {}", token_balance);
+        msg!("Admin ATA token balance (POD Read): {}", admin_balance);
 
         // Assert that the mint was successful (10,000 tokens)
         assert_eq!(
-            token_balance, 10_000,
+            admin_balance, 10_000,
             "Token balance after minting is incorrect."
         );
 
@@ -374,16 +427,8 @@ mod tests {
 
         svm.send_transaction(transaction_create_ata).unwrap();
 
-        let mint_to_ix = spl_token_2022::instruction::mint_to_checked(
-            &reusable_data.token_program.key(),
-            &reusable_data.mint.pubkey(),
-            &new_user_ata,
-            &reusable_data.admin.pubkey(),
-            &[],    // Signer Pubkeys (not needed if authority signs the tx)
-            20_000, // Amount
-            9,      // Decimals
-        )
-        .unwrap();
+        // the vault PDA is the mint authority, the admin mints through the program
+        let mint_to_ix = mint_token_ix(&reusable_data, &new_user.pubkey(), 20_000);
 
         let recent_blockhash = svm.latest_blockhash();
 
@@ -416,6 +461,12 @@ mod tests {
             "Token balance after minting is incorrect."
         );
 
+        let new_user_share_ata = associated_token::get_associated_token_address_with_program_id(
+            &new_user.pubkey(),
+            &reusable_data.share_mint,
+            &reusable_data.token_program.key(),
+        );
+
         let deposit_to_vault_ix = Instruction {
             program_id: PROGRAM_ID,
             accounts: crate::accounts::DepositWithdraw {
@@ -430,7 +481,13 @@ mod tests {
                 vault_ata: reusable_data.vault_ata.key(),
                 vault_state: reusable_data.vault_state.key(),
                 extra_account_meta_list: extra_account_meta_list.key(),
+                whitelist_entry,
+                vault_entry: reusable_data.vault_entry,
                 whitelist: whitelist.key(),
+                share_mint: reusable_data.share_mint,
+                user_share_ata: new_user_share_ata,
+                vault_program: PROGRAM_ID,
+                hook_authority: hook_authority(),
             }
             .to_account_metas(None),
             data: crate::instruction::Deposit { amount: 100 }.data(),
@@ -451,6 +508,13 @@ mod tests {
 
         svm.send_transaction(transaction5).unwrap();
 
+        let whitelist_entry_account = svm.get_account(&whitelist_entry).unwrap();
+        let fetched_entry =
+            crate::state::WhitelistEntry::try_deserialize(&mut whitelist_entry_account.data.as_ref())
+                .unwrap();
+        assert_eq!(fetched_entry.amount, 100, "Deposit wasn't recorded on the entry");
+        assert_eq!(token_balance(&svm, &new_user_share_ata), 100, "Empty vault should mint 1:1");
+
         msg!("🔥🔥🔥🔥🔥 [6] remove from whitelist");
         // 🔥🔥🔥🔥🔥 [6] remove from whitelist
         // let remove_from_whitelist_ix = Instruction {
@@ -496,7 +560,13 @@ mod tests {
                 vault_ata: reusable_data.vault_ata.key(),
                 vault_state: reusable_data.vault_state.key(),
                 extra_account_meta_list: extra_account_meta_list.key(),
+                whitelist_entry,
+                vault_entry: reusable_data.vault_entry,
                 whitelist: whitelist.key(),
+                share_mint: reusable_data.share_mint,
+                user_share_ata: new_user_share_ata,
+                vault_program: PROGRAM_ID,
+                hook_authority: hook_authority(),
             }
             .to_account_metas(None),
             data: crate::instruction::Withdraw { amount: 100 }.data(),
@@ -512,5 +582,2486 @@ mod tests {
         );
 
         svm.send_transaction(transaction7).unwrap();
+
+        // withdrawing everything keeps the user whitelisted
+        let emptied_entry = fetch_entry(&svm, &whitelist_entry);
+        assert_eq!(emptied_entry.amount, 0);
+        assert!(emptied_entry.is_whitelisted, "Zero balance shouldn't end the membership");
+    }
+    fn try_send(svm: &mut LiteSVM, ixs: &[Instruction], signers: &[&Keypair]) -> bool {
+        let recent_blockhash = svm.latest_blockhash();
+        let transaction = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&signers[0].pubkey()),
+            signers,
+            recent_blockhash,
+        );
+        svm.send_transaction(transaction).is_ok()
+    }
+
+    fn send(svm: &mut LiteSVM, ixs: &[Instruction], signers: &[&Keypair]) {
+        assert!(try_send(svm, ixs, signers), "transaction failed");
+    }
+
+    fn entry_pda(vault_state: &Pubkey, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[crate::WHITELIST_ENTRY_SEED, vault_state.as_ref(), owner.as_ref()],
+            &PROGRAM_ID,
+        )
+        .0
+    }
+
+    fn hook_authority() -> Pubkey {
+        Pubkey::find_program_address(&[crate::HOOK_AUTHORITY_SEED], &TRANSFER_HOOK_PROGRAM_ID).0
+    }
+
+    fn token_balance(svm: &LiteSVM, ata: &Pubkey) -> u64 {
+        let data = svm.get_account(ata).unwrap().data;
+        u64::from_le_bytes(data[64..72].try_into().unwrap())
+    }
+
+    // create the vault and the hook's extra account list, returns the list
+    fn create_vault_with_hook(svm: &mut LiteSVM, data: &ReusableData) -> Pubkey {
+        create_vault_with_config(svm, data, crate::state::VaultMintConfig::default())
+    }
+
+    fn create_vault_with_config(
+        svm: &mut LiteSVM,
+        data: &ReusableData,
+        config: crate::state::VaultMintConfig,
+    ) -> Pubkey {
+        let create_vault_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultOperation {
+                associated_token_program: data.ata_program,
+                hook_program_id: TRANSFER_HOOK_PROGRAM_ID,
+                mint: data.mint.pubkey(),
+                owner: data.admin.pubkey(),
+                system_program: data.system_program,
+                token_program: data.token_program,
+                vault_ata: data.vault_ata,
+                vault_state: data.vault_state,
+                vault_entry: data.vault_entry,
+                whitelist: data.whitelist,
+                share_mint: data.share_mint,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::CreateVault { config }.data(),
+        };
+        send(svm, &[create_vault_ix], &[&data.admin, &data.mint]);
+
+        let extra_account_meta_list = Pubkey::find_program_address(
+            &[b"extra-account-metas", data.mint.pubkey().as_ref()],
+            &TRANSFER_HOOK_PROGRAM_ID,
+        )
+        .0;
+        let initialize_transfer_hook_ix = Instruction {
+            program_id: TRANSFER_HOOK_PROGRAM_ID,
+            accounts: transfer_hook::accounts::InitializeExtraAccountMetaList {
+                extra_account_meta_list,
+                mint: data.mint.pubkey(),
+                payer: data.admin.pubkey(),
+                system_program: data.system_program,
+            }
+            .to_account_metas(None),
+            data: transfer_hook::instruction::InitializeTransferHook {}.data(),
+        };
+        send(svm, &[initialize_transfer_hook_ix], &[&data.admin]);
+
+        extra_account_meta_list
+    }
+
+    // funded user with an empty ATA for the vault mint
+    fn new_user_with_ata(svm: &mut LiteSVM, data: &ReusableData) -> (Keypair, Pubkey) {
+        let user = Keypair::new();
+        svm.airdrop(&user.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+        let create_ata_ix =
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &user.pubkey(),
+                &user.pubkey(),
+                &data.mint.pubkey(),
+                &data.token_program,
+            );
+        send(svm, &[create_ata_ix], &[&user]);
+        let ata = associated_token::get_associated_token_address_with_program_id(
+            &user.pubkey(),
+            &data.mint.pubkey(),
+            &data.token_program,
+        );
+        (user, ata)
+    }
+
+    fn share_ata_of(data: &ReusableData, owner: &Pubkey) -> Pubkey {
+        associated_token::get_associated_token_address_with_program_id(
+            owner,
+            &data.share_mint,
+            &data.token_program,
+        )
+    }
+
+    fn create_share_ata(svm: &mut LiteSVM, data: &ReusableData, user: &Keypair) -> Pubkey {
+        let create_ata_ix =
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &user.pubkey(),
+                &user.pubkey(),
+                &data.share_mint,
+                &data.token_program,
+            );
+        send(svm, &[create_ata_ix], &[user]);
+        associated_token::get_associated_token_address_with_program_id(
+            &user.pubkey(),
+            &data.share_mint,
+            &data.token_program,
+        )
+    }
+
+    // accounts of a vault created by the pre-upgrade programs: a hook mint minted by the owner,
+    // the three-field vault, its ata holding `deposited` and a one-meta ExtraAccountMetaList
+    fn create_legacy_vault(svm: &mut LiteSVM, data: &ReusableData, deposited: u64) -> Pubkey {
+        use anchor_lang::{AnchorSerialize, Discriminator};
+        use spl_token_2022::extension::{transfer_hook, ExtensionType};
+        use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+        use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+        let admin = data.admin.pubkey();
+        let mint = data.mint.pubkey();
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::TransferHook])
+                .unwrap();
+        let create_mint_ixs = [
+            anchor_lang::solana_program::system_instruction::create_account(
+                &admin,
+                &mint,
+                svm.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &data.token_program,
+            ),
+            transfer_hook::instruction::initialize(
+                &data.token_program,
+                &mint,
+                Some(admin),
+                Some(TRANSFER_HOOK_PROGRAM_ID),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(&data.token_program, &mint, &admin, None, 9)
+                .unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &admin,
+                &data.vault_state,
+                &mint,
+                &data.token_program,
+            ),
+            spl_token_2022::instruction::mint_to(
+                &data.token_program,
+                &mint,
+                &data.vault_ata,
+                &admin,
+                &[],
+                deposited,
+            )
+            .unwrap(),
+        ];
+        send(svm, &create_mint_ixs, &[&data.admin, &data.mint]);
+
+        let vault_bump = Pubkey::find_program_address(&[mint.as_ref(), crate::VAULT_SEED], &PROGRAM_ID).1;
+        let mut vault_data = crate::state::Vault::DISCRIMINATOR.to_vec();
+        crate::state::VaultV0 { mint, vault_bump, owner: admin }
+            .serialize(&mut vault_data)
+            .unwrap();
+        set_program_account(svm, data.vault_state, vault_data, PROGRAM_ID);
+
+        // the old hook only knew the global whitelist
+        let extra_account_meta_list = Pubkey::find_program_address(
+            &[b"extra-account-metas", mint.as_ref()],
+            &TRANSFER_HOOK_PROGRAM_ID,
+        )
+        .0;
+        let global_whitelist = Pubkey::find_program_address(&[crate::WHITELIST_SEED], &PROGRAM_ID).0;
+        let mut list_data = vec![0; ExtraAccountMetaList::size_of(1).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut list_data,
+            &[ExtraAccountMeta::new_with_pubkey(&global_whitelist, false, true).unwrap()],
+        )
+        .unwrap();
+        set_program_account(svm, extra_account_meta_list, list_data, TRANSFER_HOOK_PROGRAM_ID);
+
+        extra_account_meta_list
+    }
+
+    fn set_program_account(svm: &mut LiteSVM, address: Pubkey, data: Vec<u8>, owner: Pubkey) {
+        svm.set_account(
+            address,
+            Account {
+                lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+                data,
+                owner,
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+        .unwrap();
+    }
+
+    fn migrate_vault_ix(data: &ReusableData) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::MigrateVault {
+                owner: data.admin.pubkey(),
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                share_mint: data.share_mint,
+                whitelist: data.whitelist,
+                vault_entry: data.vault_entry,
+                system_program: data.system_program,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::MigrateVault {}.data(),
+        }
+    }
+
+    fn update_extra_account_meta_list_ix(data: &ReusableData, extra_account_meta_list: Pubkey) -> Instruction {
+        Instruction {
+            program_id: TRANSFER_HOOK_PROGRAM_ID,
+            accounts: transfer_hook::accounts::UpdateExtraAccountMetaList {
+                extra_account_meta_list,
+                mint: data.mint.pubkey(),
+                authority: data.admin.pubkey(),
+                system_program: data.system_program,
+            }
+            .to_account_metas(None),
+            data: transfer_hook::instruction::UpdateExtraAccountMetaList {}.data(),
+        }
+    }
+
+    // write the pre-upgrade whitelist vec straight into the account
+    fn set_legacy_whitelist(svm: &mut LiteSVM, data: &ReusableData, records: Vec<(Pubkey, u64, bool)>) -> Pubkey {
+        // the pre-upgrade whitelist was one global account
+        let (whitelist, whitelist_bump) = Pubkey::find_program_address(&[crate::WHITELIST_SEED], &PROGRAM_ID);
+        let len = records.len();
+        let mut account_data = Vec::new();
+        crate::state::Whitelist {
+            address: records,
+            whitelist_bump,
+            admin: data.admin.pubkey(),
+        }
+        .try_serialize(&mut account_data)
+        .unwrap();
+        assert_eq!(account_data.len(), crate::state::Whitelist::space_for(len));
+
+        set_program_account(svm, whitelist, account_data, PROGRAM_ID);
+        whitelist
+    }
+
+    fn fetch_whitelist(svm: &LiteSVM, whitelist: &Pubkey) -> (crate::state::Whitelist, usize) {
+        let account = svm.get_account(whitelist).unwrap();
+        let fetched =
+            crate::state::Whitelist::try_deserialize(&mut account.data.as_ref()).unwrap();
+        (fetched, account.data.len())
+    }
+
+    fn fetch_entry(svm: &LiteSVM, entry: &Pubkey) -> crate::state::WhitelistEntry {
+        let account = svm.get_account(entry).unwrap();
+        crate::state::WhitelistEntry::try_deserialize(&mut account.data.as_ref()).unwrap()
+    }
+
+    fn migrate_ix(data: &ReusableData, whitelist: Pubkey, pairs: &[(Pubkey, Pubkey, Pubkey)]) -> Instruction {
+        let mut accounts = crate::accounts::MigrateWhitelist {
+            admin: data.admin.pubkey(),
+            legacy_whitelist: whitelist,
+            vault: data.vault_state,
+            share_mint: data.share_mint,
+            system_program: data.system_program,
+            token_program: data.token_program,
+        }
+        .to_account_metas(None);
+        for (user_ata, entry, share_ata) in pairs {
+            accounts.push(AccountMeta::new_readonly(*user_ata, false));
+            accounts.push(AccountMeta::new(*entry, false));
+            accounts.push(AccountMeta::new(*share_ata, false));
+        }
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: crate::instruction::MigrateWhitelistChunk {
+                _mint: data.mint.pubkey(),
+            }
+            .data(),
+        }
+    }
+
+    #[test]
+    pub fn test_partially_migrated_whitelist() {
+        let (mut svm, data) = setup();
+        // balances deposited before the upgrade sit in the vault ata
+        let extra_account_meta_list = create_legacy_vault(&mut svm, &data, 500);
+
+        let (user_a, ata_a) = new_user_with_ata(&mut svm, &data);
+        let (user_b, ata_b) = new_user_with_ata(&mut svm, &data);
+        let whitelist =
+            set_legacy_whitelist(&mut svm, &data, vec![(ata_a, 200, true), (ata_b, 300, true)]);
+        let withdraw_legacy_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::LegacyWithdraw {
+                sender: user_b.pubkey(),
+                mint: data.mint.pubkey(),
+                hook_program_id: TRANSFER_HOOK_PROGRAM_ID,
+                legacy_whitelist: whitelist,
+                vault_state: data.vault_state,
+                vault_entry: data.vault_entry,
+                whitelist: data.whitelist,
+                vault_ata: data.vault_ata,
+                user_ata: ata_b,
+                share_mint: data.share_mint,
+                extra_account_meta_list,
+                vault_program: PROGRAM_ID,
+                hook_authority: hook_authority(),
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::WithdrawLegacy { amount: 300 }.data(),
+        };
+
+        // nothing works against the old vault layout
+        assert!(!try_send(&mut svm, &[withdraw_legacy_ix.clone()], &[&user_b]));
+
+        send(&mut svm, &[migrate_vault_ix(&data)], &[&data.admin]);
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.version, crate::VAULT_VERSION);
+        assert_eq!(vault.owner, data.admin.pubkey());
+        assert_eq!(vault.total_assets, 0);
+        assert_eq!(mint_authorities(&svm, &data).0, Some(data.vault_state));
+        assert!(fetch_entry(&svm, &data.vault_entry).is_whitelisted);
+        svm.expire_blockhash();
+        assert!(!try_send(&mut svm, &[migrate_vault_ix(&data)], &[&data.admin]));
+
+        // the old meta list still points the hook at the global whitelist
+        assert!(!try_send(&mut svm, &[withdraw_legacy_ix.clone()], &[&user_b]));
+        send(
+            &mut svm,
+            &[update_extra_account_meta_list_ix(&data, extra_account_meta_list)],
+            &[&data.admin],
+        );
+
+        // migrate only user A
+        let entry_a = entry_pda(&data.vault_state, &user_a.pubkey());
+        let entry_b = entry_pda(&data.vault_state, &user_b.pubkey());
+        let share_ata_a = create_share_ata(&mut svm, &data, &user_a);
+        let share_ata_b = create_share_ata(&mut svm, &data, &user_b);
+        send(&mut svm, &[migrate_ix(&data, whitelist, &[(ata_a, entry_a, share_ata_a)])], &[&data.admin]);
+        assert_eq!(token_balance(&svm, &share_ata_a), 200, "migrated balance should be backed by shares");
+
+        let migrated = fetch_entry(&svm, &entry_a);
+        assert_eq!(migrated.owner, user_a.pubkey());
+        assert_eq!(migrated.amount, 200);
+        assert!(migrated.is_whitelisted);
+
+        let (fetched_whitelist, whitelist_len) = fetch_whitelist(&svm, &whitelist);
+        assert_eq!(fetched_whitelist.address, vec![(ata_b, 300, true)]);
+        assert_eq!(whitelist_len, crate::state::Whitelist::space_for(1), "vec account wasn't shrunk");
+        assert!(svm.get_account(&entry_b).is_none());
+
+        // user B is still in the vec and withdraws through the legacy path, unless there is a cooldown
+        let set_cooldown_ix = |seconds: i64| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultConfig {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetWithdrawCooldown { seconds }.data(),
+        };
+        send(&mut svm, &[set_cooldown_ix(3_600)], &[&data.admin]);
+        assert!(!try_send(&mut svm, &[withdraw_legacy_ix.clone()], &[&user_b]));
+        send(&mut svm, &[set_cooldown_ix(0)], &[&data.admin]);
+        svm.expire_blockhash();
+        send(&mut svm, &[withdraw_legacy_ix], &[&user_b]);
+        assert_eq!(token_balance(&svm, &ata_b), 300);
+
+        // user A withdraws through the entry
+        let withdraw_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::DepositWithdraw {
+                associated_token_program: data.ata_program,
+                hook_program_id: TRANSFER_HOOK_PROGRAM_ID,
+                mint: data.mint.pubkey(),
+                owner: data.admin.pubkey(),
+                sender: user_a.pubkey(),
+                system_program: data.system_program,
+                token_program: data.token_program,
+                user_ata: ata_a,
+                vault_ata: data.vault_ata,
+                vault_state: data.vault_state,
+                extra_account_meta_list,
+                whitelist_entry: entry_a,
+                vault_entry: data.vault_entry,
+                whitelist: data.whitelist,
+                share_mint: data.share_mint,
+                user_share_ata: share_ata_a,
+                vault_program: PROGRAM_ID,
+                hook_authority: hook_authority(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Withdraw { amount: 200 }.data(),
+        };
+        send(&mut svm, &[withdraw_ix], &[&user_a]);
+        assert_eq!(token_balance(&svm, &ata_a), 200);
+        assert_eq!(token_balance(&svm, &data.vault_ata), 0);
+
+        // lamports sent to an entry address ahead of time don't block its record
+        let dust_ix =
+            anchor_lang::solana_program::system_instruction::transfer(&user_a.pubkey(), &entry_b, 1_000);
+        send(&mut svm, &[dust_ix], &[&user_a]);
+
+        // finish the migration
+        send(&mut svm, &[migrate_ix(&data, whitelist, &[(ata_b, entry_b, share_ata_b)])], &[&data.admin]);
+        let migrated = fetch_entry(&svm, &entry_b);
+        assert_eq!(migrated.owner, user_b.pubkey());
+        assert_eq!(migrated.amount, 0);
+
+        let (fetched_whitelist, whitelist_len) = fetch_whitelist(&svm, &whitelist);
+        assert!(fetched_whitelist.address.is_empty());
+        assert_eq!(whitelist_len, crate::state::Whitelist::space_for(0));
+    }
+    fn add_to_whitelist_ix(data: &ReusableData, admin: &Pubkey, address: &Pubkey) -> Instruction {
+        whitelist_until_ix(data, admin, address, None)
+    }
+
+    fn whitelist_until_ix(
+        data: &ReusableData,
+        admin: &Pubkey,
+        address: &Pubkey,
+        expires_at: Option<i64>,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::WhitelistOperations {
+                whitelist: data.whitelist,
+                whitelist_entry: entry_pda(&data.vault_state, address),
+                vault: data.vault_state,
+                share_mint: data.share_mint,
+                owner_share_ata: share_ata_of(data, address),
+                admin: *admin,
+                system_program: data.system_program,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::AddToWhitelist {
+                address: *address,
+                _mint: data.mint.pubkey(),
+                expires_at,
+            }
+            .data(),
+        }
+    }
+
+    #[test]
+    pub fn test_vault_whitelists_are_isolated() {
+        let (mut svm, vault_a) = setup();
+        create_vault_with_hook(&mut svm, &vault_a);
+        let vault_b = vault_data(&mut svm);
+        create_vault_with_hook(&mut svm, &vault_b);
+
+        assert_ne!(vault_a.whitelist, vault_b.whitelist);
+        let (whitelist_a, _) = fetch_whitelist(&svm, &vault_a.whitelist);
+        let (whitelist_b, _) = fetch_whitelist(&svm, &vault_b.whitelist);
+        assert_eq!(whitelist_a.admin, vault_a.admin.pubkey());
+        assert_eq!(whitelist_b.admin, vault_b.admin.pubkey());
+
+        let user = Keypair::new();
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&vault_a, &vault_a.admin.pubkey(), &user.pubkey())],
+            &[&vault_a.admin],
+        );
+        assert!(fetch_entry(&svm, &entry_pda(&vault_a.vault_state, &user.pubkey())).is_whitelisted);
+        // being whitelisted in A says nothing about B
+        assert!(svm
+            .get_account(&entry_pda(&vault_b.vault_state, &user.pubkey()))
+            .is_none());
+
+        // B's admin can't touch A's whitelist
+        let intruder = Keypair::new();
+        assert!(!try_send(
+            &mut svm,
+            &[add_to_whitelist_ix(&vault_a, &vault_b.admin.pubkey(), &intruder.pubkey())],
+            &[&vault_b.admin],
+        ));
+        assert!(svm
+            .get_account(&entry_pda(&vault_a.vault_state, &intruder.pubkey()))
+            .is_none());
+
+        // but manages their own
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&vault_b, &vault_b.admin.pubkey(), &intruder.pubkey())],
+            &[&vault_b.admin],
+        );
+        assert!(fetch_entry(&svm, &entry_pda(&vault_b.vault_state, &intruder.pubkey())).is_whitelisted);
+    }
+    #[test]
+    pub fn test_hook_resolves_source_owner_entry() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user_a, ata_a) = new_user_with_ata(&mut svm, &data);
+        let (user_b, ata_b) = new_user_with_ata(&mut svm, &data);
+        let (outsider, outsider_ata) = new_user_with_ata(&mut svm, &data);
+        for user in [&user_a, &user_b] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+                &[&data.admin],
+            );
+        }
+        mint_to_user(&mut svm, &data, &user_a.pubkey(), 1_000);
+        let transfer = |from: &Keypair, to: &Keypair, amount: u64| {
+            hooked_transfer_ix(&data, extra_account_meta_list, from, &to.pubkey(), amount)
+        };
+
+        // each direction is checked against the sender's own entry
+        send(&mut svm, &[transfer(&user_a, &user_b, 300)], &[&user_a]);
+        send(&mut svm, &[transfer(&user_b, &user_a, 100)], &[&user_b]);
+        assert_eq!(token_balance(&svm, &ata_a), 800);
+        assert_eq!(token_balance(&svm, &ata_b), 200);
+
+        // the list derives the entry from the source account's owner, any other entry is refused
+        let mut wrong_entry_ix = transfer(&user_a, &user_b, 10);
+        assert_eq!(wrong_entry_ix.accounts[7].pubkey, entry_pda(&data.vault_state, &user_a.pubkey()));
+        wrong_entry_ix.accounts[7].pubkey = entry_pda(&data.vault_state, &user_b.pubkey());
+        assert!(!try_send(&mut svm, &[wrong_entry_ix], &[&user_a]));
+
+        // receiving needs no entry, sending does
+        send(&mut svm, &[transfer(&user_a, &outsider, 50)], &[&user_a]);
+        assert_eq!(token_balance(&svm, &outsider_ata), 50);
+        assert!(!try_send(&mut svm, &[transfer(&outsider, &user_a, 50)], &[&outsider]));
+        assert_eq!(token_balance(&svm, &ata_a), 750);
+    }
+    fn mint_token_ix(data: &ReusableData, recipient: &Pubkey, amount: u64) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::TokenFactory {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+                mint: data.mint.pubkey(),
+                recipient: *recipient,
+                recipient_entry: entry_pda(&data.vault_state, recipient),
+                recipient_ata: associated_token::get_associated_token_address_with_program_id(
+                    recipient,
+                    &data.mint.pubkey(),
+                    &data.token_program,
+                ),
+                system_program: data.system_program,
+                token_program: data.token_program,
+                associated_token_program: data.ata_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::MintToken { amount }.data(),
+        }
+    }
+
+    // the recipient has to be whitelisted already
+    fn mint_to_user(svm: &mut LiteSVM, data: &ReusableData, recipient: &Pubkey, amount: u64) {
+        send(svm, &[mint_token_ix(data, recipient, amount)], &[&data.admin]);
+    }
+
+    // deposit or withdraw by `user`, amount is tokens for deposit and shares for withdraw
+    fn deposit_withdraw_ix(
+        data: &ReusableData,
+        extra_account_meta_list: Pubkey,
+        user: &Pubkey,
+        ix_data: Vec<u8>,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::DepositWithdraw {
+                associated_token_program: data.ata_program,
+                hook_program_id: TRANSFER_HOOK_PROGRAM_ID,
+                mint: data.mint.pubkey(),
+                owner: data.admin.pubkey(),
+                sender: *user,
+                system_program: data.system_program,
+                token_program: data.token_program,
+                user_ata: associated_token::get_associated_token_address_with_program_id(
+                    user,
+                    &data.mint.pubkey(),
+                    &data.token_program,
+                ),
+                vault_ata: data.vault_ata,
+                vault_state: data.vault_state,
+                extra_account_meta_list,
+                whitelist_entry: entry_pda(&data.vault_state, user),
+                vault_entry: data.vault_entry,
+                whitelist: data.whitelist,
+                share_mint: data.share_mint,
+                user_share_ata: associated_token::get_associated_token_address_with_program_id(
+                    user,
+                    &data.share_mint,
+                    &data.token_program,
+                ),
+                vault_program: PROGRAM_ID,
+                hook_authority: hook_authority(),
+            }
+            .to_account_metas(None),
+            data: ix_data,
+        }
+    }
+
+    fn fetch_vault(svm: &LiteSVM, data: &ReusableData) -> crate::state::Vault {
+        let account = svm.get_account(&data.vault_state).unwrap();
+        crate::state::Vault::try_deserialize(&mut account.data.as_ref()).unwrap()
+    }
+
+    #[test]
+    pub fn test_shares_are_transferable_positions() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user_a, _ata_a) = new_user_with_ata(&mut svm, &data);
+        let (user_b, ata_b) = new_user_with_ata(&mut svm, &data);
+        for user in [&user_a, &user_b] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+                &[&data.admin],
+            );
+        }
+        mint_to_user(&mut svm, &data, &user_a.pubkey(), 1_000);
+
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user_a.pubkey(),
+                crate::instruction::Deposit { amount: 600 }.data(),
+            )],
+            &[&user_a],
+        );
+        let share_ata_a = associated_token::get_associated_token_address_with_program_id(
+            &user_a.pubkey(),
+            &data.share_mint,
+            &data.token_program,
+        );
+        assert_eq!(token_balance(&svm, &share_ata_a), 600);
+        assert_eq!(fetch_vault(&svm, &data).total_assets, 600);
+
+        // hand half the position to B, shares carry no hook
+        let share_ata_b = create_share_ata(&mut svm, &data, &user_b);
+        let transfer_ix = spl_token_2022::instruction::transfer_checked(
+            &data.token_program,
+            &share_ata_a,
+            &data.share_mint,
+            &share_ata_b,
+            &user_a.pubkey(),
+            &[],
+            300,
+            9,
+        )
+        .unwrap();
+        send(&mut svm, &[transfer_ix], &[&user_a]);
+
+        // B never deposited but redeems the shares they were given
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user_b.pubkey(),
+                crate::instruction::Withdraw { amount: 300 }.data(),
+            )],
+            &[&user_b],
+        );
+        assert_eq!(token_balance(&svm, &ata_b), 300);
+        assert_eq!(token_balance(&svm, &share_ata_b), 0);
+
+        // A can't redeem more shares than they still hold
+        assert!(!try_send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user_a.pubkey(),
+                crate::instruction::Withdraw { amount: 301 }.data(),
+            )],
+            &[&user_a],
+        ));
+
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.total_assets, 300);
+        assert_eq!(token_balance(&svm, &data.vault_ata), 300);
+    }
+    #[test]
+    pub fn test_share_exchange_rate() {
+        let vault = crate::state::Vault {
+            mint: Pubkey::new_unique(),
+            vault_bump: 255,
+            owner: Pubkey::new_unique(),
+            share_mint_bump: 255,
+            total_assets: 1_500,
+            pending_withdrawals: 0,
+            withdraw_cooldown: 0,
+            tvl_cap: None,
+            user_cap: None,
+            pauser: Pubkey::new_unique(),
+            paused_deposits: false,
+            paused_withdrawals: false,
+            paused_transfers: false,
+            pending_owner: Pubkey::default(),
+            max_supply: None,
+            emission: None,
+            total_minted: 0,
+            pending_shares: 0,
+            whitelist_root: None,
+            last_reconciliation: None,
+            flash_fee_bps: None,
+            flash_loan: None,
+            vault_transfer: false,
+            version: crate::VAULT_VERSION,
+        };
+        // 1000 shares backed by 1500 assets after yield
+        assert_eq!(vault.shares_for_assets(300, 1_000), Some(200));
+        assert_eq!(vault.assets_for_shares(200, 1_000), Some(300));
+        // rounds down in the vault's favour
+        assert_eq!(vault.shares_for_assets(1, 1_000), Some(0));
+        // empty vault mints 1:1
+        assert_eq!(vault.shares_for_assets(42, 0), Some(42));
+        assert_eq!(vault.assets_for_shares(42, 0), None);
+    }
+    #[test]
+    pub fn test_withdraw_cooldown() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user, user_ata) = new_user_with_ata(&mut svm, &data);
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+            &[&data.admin],
+        );
+        mint_to_user(&mut svm, &data, &user.pubkey(), 500);
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user.pubkey(),
+                crate::instruction::Deposit { amount: 500 }.data(),
+            )],
+            &[&user],
+        );
+
+        let set_cooldown_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultConfig {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetWithdrawCooldown { seconds: 3_600 }.data(),
+        };
+        send(&mut svm, &[set_cooldown_ix], &[&data.admin]);
+
+        // instant withdraw is gone once a cooldown is set
+        assert!(!try_send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user.pubkey(),
+                crate::instruction::Withdraw { amount: 200 }.data(),
+            )],
+            &[&user],
+        ));
+
+        let share_ata = associated_token::get_associated_token_address_with_program_id(
+            &user.pubkey(),
+            &data.share_mint,
+            &data.token_program,
+        );
+        let withdraw_request = Pubkey::find_program_address(
+            &[
+                crate::WITHDRAW_REQUEST_SEED,
+                data.vault_state.as_ref(),
+                user.pubkey().as_ref(),
+            ],
+            &PROGRAM_ID,
+        )
+        .0;
+
+        let request_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::RequestWithdraw {
+                sender: user.pubkey(),
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                whitelist_entry: entry_pda(&data.vault_state, &user.pubkey()),
+                share_mint: data.share_mint,
+                user_share_ata: share_ata,
+                withdraw_request,
+                system_program: data.system_program,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::RequestWithdraw { amount: 200 }.data(),
+        };
+        send(&mut svm, &[request_ix], &[&user]);
+        assert_eq!(token_balance(&svm, &share_ata), 300, "requested shares should be burnt");
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.total_assets, 300);
+        assert_eq!(vault.pending_withdrawals, 200);
+
+        // the requested amount is still owed out of vault_ata, the books stay balanced
+        send(&mut svm, &[reconcile_ix(&data, &user, true)], &[&user]);
+        let last = fetch_vault(&svm, &data).last_reconciliation.unwrap();
+        assert_eq!((last.liabilities, last.holdings), (500, 500));
+
+        let account = svm.get_account(&withdraw_request).unwrap();
+        let request =
+            crate::state::WithdrawRequest::try_deserialize(&mut account.data.as_ref()).unwrap();
+        assert_eq!(request.amount, 200);
+
+        let claim_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ClaimWithdraw {
+                sender: user.pubkey(),
+                mint: data.mint.pubkey(),
+                hook_program_id: TRANSFER_HOOK_PROGRAM_ID,
+                vault_state: data.vault_state,
+                vault_entry: data.vault_entry,
+                whitelist: data.whitelist,
+                withdraw_request,
+                vault_ata: data.vault_ata,
+                user_ata,
+                share_mint: data.share_mint,
+                extra_account_meta_list,
+                vault_program: PROGRAM_ID,
+                hook_authority: hook_authority(),
+                token_program: data.token_program,
+                associated_token_program: data.ata_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ClaimWithdraw {}.data(),
+        };
+
+        // still locked
+        assert!(!try_send(&mut svm, &[claim_ix.clone()], &[&user]));
+
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = request.unlock_at;
+        svm.set_sysvar(&clock);
+        svm.expire_blockhash();
+
+        send(&mut svm, &[claim_ix], &[&user]);
+        assert_eq!(token_balance(&svm, &user_ata), 200);
+        assert_eq!(fetch_vault(&svm, &data).pending_withdrawals, 0);
+        assert!(svm
+            .get_account(&withdraw_request)
+            .map_or(true, |account| account.lamports == 0));
+    }
+    #[test]
+    pub fn test_deposit_caps() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user_a, _ata_a) = new_user_with_ata(&mut svm, &data);
+        let (user_b, _ata_b) = new_user_with_ata(&mut svm, &data);
+        for user in [&user_a, &user_b] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+                &[&data.admin],
+            );
+            mint_to_user(&mut svm, &data, &user.pubkey(), 1_000);
+        }
+        let deposit = |user: &Keypair, amount: u64| {
+            deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user.pubkey(),
+                crate::instruction::Deposit { amount }.data(),
+            )
+        };
+
+        let set_caps_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultConfig {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetCaps {
+                tvl_cap: Some(1_000),
+                user_cap: Some(400),
+            }
+            .data(),
+        };
+        send(&mut svm, &[set_caps_ix], &[&data.admin]);
+
+        // vault default applies to A
+        send(&mut svm, &[deposit(&user_a, 400)], &[&user_a]);
+        assert!(!try_send(&mut svm, &[deposit(&user_a, 1)], &[&user_a]));
+
+        // B gets a bigger cap of their own
+        let set_user_cap_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetUserCap {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+                whitelist_entry: entry_pda(&data.vault_state, &user_b.pubkey()),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetUserCap { cap: Some(700) }.data(),
+        };
+        send(&mut svm, &[set_user_cap_ix], &[&data.admin]);
+
+        // within B's cap but over the TVL cap
+        assert!(!try_send(&mut svm, &[deposit(&user_b, 601)], &[&user_b]));
+        send(&mut svm, &[deposit(&user_b, 600)], &[&user_b]);
+
+        assert_eq!(fetch_vault(&svm, &data).total_assets, 1_000);
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &user_b.pubkey())).cap, Some(700));
+    }
+    // plain hooked transfer between two users, with the accounts the hook resolves
+    fn hooked_transfer_ix(
+        data: &ReusableData,
+        extra_account_meta_list: Pubkey,
+        from: &Keypair,
+        to: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let token_program = data.token_program;
+        let mint = data.mint.pubkey();
+        let source = associated_token::get_associated_token_address_with_program_id(
+            &from.pubkey(),
+            &mint,
+            &token_program,
+        );
+        let destination =
+            associated_token::get_associated_token_address_with_program_id(to, &mint, &token_program);
+        let mut ix = spl_token_2022::instruction::transfer_checked(
+            &token_program,
+            &source,
+            &mint,
+            &destination,
+            &from.pubkey(),
+            &[],
+            amount,
+            9,
+        )
+        .unwrap();
+        ix.accounts.extend([
+            AccountMeta::new_readonly(extra_account_meta_list, false),
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+            AccountMeta::new(data.vault_state, false),
+            AccountMeta::new(entry_pda(&data.vault_state, &from.pubkey()), false),
+            AccountMeta::new_readonly(data.whitelist, false),
+            AccountMeta::new_readonly(data.share_mint, false),
+            AccountMeta::new_readonly(hook_authority(), false),
+            AccountMeta::new_readonly(TRANSFER_HOOK_PROGRAM_ID, false),
+        ]);
+        ix
+    }
+
+    #[test]
+    pub fn test_pause_switches() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user_a, ata_a) = new_user_with_ata(&mut svm, &data);
+        let (user_b, _ata_b) = new_user_with_ata(&mut svm, &data);
+        for user in [&user_a, &user_b] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+                &[&data.admin],
+            );
+        }
+        mint_to_user(&mut svm, &data, &user_a.pubkey(), 1_000);
+        let deposit = |amount: u64| {
+            deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user_a.pubkey(),
+                crate::instruction::Deposit { amount }.data(),
+            )
+        };
+        let config_ix = |ix_data: Vec<u8>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultConfig {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: ix_data,
+        };
+        let pause_ix = |authority: &Pubkey, deposits: bool, transfers: bool| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Pause {
+                authority: *authority,
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Pause {
+                deposits,
+                withdrawals: false,
+                transfers,
+            }
+            .data(),
+        };
+
+        let pauser = Keypair::new();
+        svm.airdrop(&pauser.pubkey(), LAMPORTS_PER_SOL).unwrap();
+        send(
+            &mut svm,
+            &[config_ix(crate::instruction::SetPauser { pauser: pauser.pubkey() }.data())],
+            &[&data.admin],
+        );
+
+        // nobody else can pause
+        assert!(!try_send(&mut svm, &[pause_ix(&user_b.pubkey(), true, false)], &[&user_b]));
+
+        send(&mut svm, &[pause_ix(&pauser.pubkey(), true, false)], &[&pauser]);
+        assert!(fetch_vault(&svm, &data).paused_deposits);
+        assert!(!try_send(&mut svm, &[deposit(100)], &[&user_a]));
+
+        // the pauser can't undo it, the owner can
+        let unpause_deposits = crate::instruction::Unpause {
+            deposits: true,
+            withdrawals: false,
+            transfers: false,
+        };
+        let pauser_unpause_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultConfig {
+                owner: pauser.pubkey(),
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: unpause_deposits.data(),
+        };
+        assert!(!try_send(&mut svm, &[pauser_unpause_ix], &[&pauser]));
+        send(&mut svm, &[config_ix(unpause_deposits.data())], &[&data.admin]);
+        send(&mut svm, &[deposit(100)], &[&user_a]);
+
+        // transfer pause stops user to user transfers in the hook
+        send(&mut svm, &[pause_ix(&pauser.pubkey(), false, true)], &[&pauser]);
+        let transfer_ix =
+            hooked_transfer_ix(&data, extra_account_meta_list, &user_a, &user_b.pubkey(), 10);
+        assert!(!try_send(&mut svm, &[transfer_ix.clone()], &[&user_a]));
+
+        // but not the vault's own withdrawals
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user_a.pubkey(),
+                crate::instruction::Withdraw { amount: 50 }.data(),
+            )],
+            &[&user_a],
+        );
+
+        send(
+            &mut svm,
+            &[config_ix(
+                crate::instruction::Unpause {
+                    deposits: false,
+                    withdrawals: false,
+                    transfers: true,
+                }
+                .data(),
+            )],
+            &[&data.admin],
+        );
+        svm.expire_blockhash();
+        send(&mut svm, &[transfer_ix], &[&user_a]);
+        assert_eq!(token_balance(&svm, &ata_a), 1_000 - 100 + 50 - 10);
+    }
+    // (mint authority, transfer hook authority) of the vault mint
+    fn mint_authorities(svm: &LiteSVM, data: &ReusableData) -> (Option<Pubkey>, Option<Pubkey>) {
+        use spl_token_2022::extension::{
+            transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions,
+        };
+        let account = svm.get_account(&data.mint.pubkey()).unwrap();
+        let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).unwrap();
+        let hook = mint.get_extension::<TransferHook>().unwrap();
+        (mint.base.mint_authority.into(), Option::<Pubkey>::from(hook.authority))
+    }
+
+    #[test]
+    pub fn test_two_step_admin_transfer() {
+        let (mut svm, data) = setup();
+        create_vault_with_hook(&mut svm, &data);
+
+        let new_admin = Keypair::new();
+        svm.airdrop(&new_admin.pubkey(), LAMPORTS_PER_SOL).unwrap();
+        let stranger = Keypair::new();
+        svm.airdrop(&stranger.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+        let accept_ix = |signer: &Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::AcceptAdmin {
+                new_owner: *signer,
+                vault_state: data.vault_state,
+                whitelist: data.whitelist,
+                mint: data.mint.pubkey(),
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::AcceptAdmin {}.data(),
+        };
+
+        let owner_ix = |ix_data: Vec<u8>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ProposeAdmin {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+                mint: data.mint.pubkey(),
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: ix_data,
+        };
+        let propose_ix = |new_owner: Pubkey| owner_ix(crate::instruction::ProposeAdmin { new_owner }.data());
+        let cancel_ix = || owner_ix(crate::instruction::CancelAdmin {}.data());
+
+        // nothing proposed yet
+        assert!(!try_send(&mut svm, &[accept_ix(&new_admin.pubkey())], &[&new_admin]));
+        assert!(!try_send(&mut svm, &[cancel_ix()], &[&data.admin]));
+        assert!(!try_send(&mut svm, &[propose_ix(Pubkey::default())], &[&data.admin]));
+
+        // a cancelled proposal gives the authorities back and can't be accepted
+        send(&mut svm, &[propose_ix(stranger.pubkey())], &[&data.admin]);
+        send(&mut svm, &[cancel_ix()], &[&data.admin]);
+        assert_eq!(fetch_vault(&svm, &data).pending_owner, Pubkey::default());
+        assert_eq!(
+            mint_authorities(&svm, &data),
+            (Some(data.vault_state), Some(data.admin.pubkey()))
+        );
+        assert!(!try_send(&mut svm, &[accept_ix(&stranger.pubkey())], &[&stranger]));
+
+        send(&mut svm, &[propose_ix(new_admin.pubkey())], &[&data.admin]);
+
+        // the vault holds the authorities while the proposal is pending
+        assert_eq!(
+            mint_authorities(&svm, &data),
+            (Some(data.vault_state), Some(data.vault_state))
+        );
+        assert_eq!(fetch_vault(&svm, &data).owner, data.admin.pubkey());
+
+        assert!(!try_send(&mut svm, &[accept_ix(&stranger.pubkey())], &[&stranger]));
+        send(&mut svm, &[accept_ix(&new_admin.pubkey())], &[&new_admin]);
+
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.owner, new_admin.pubkey());
+        assert_eq!(vault.pending_owner, Pubkey::default());
+        assert_eq!(vault.pauser, new_admin.pubkey());
+        assert_eq!(fetch_whitelist(&svm, &data.whitelist).0.admin, new_admin.pubkey());
+        assert_eq!(
+            mint_authorities(&svm, &data),
+            (Some(data.vault_state), Some(new_admin.pubkey()))
+        );
+
+        // only the new admin runs the whitelist now
+        let user = Keypair::new();
+        assert!(!try_send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+            &[&data.admin],
+        ));
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &new_admin.pubkey(), &user.pubkey())],
+            &[&new_admin],
+        );
+    }
+    #[test]
+    pub fn test_create_vault_with_extensions() {
+        use {
+            anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata,
+            spl_token_2022::{
+                extension::{
+                    interest_bearing_mint::InterestBearingConfig, BaseStateWithExtensions,
+                    ExtensionType, StateWithExtensions,
+                },
+                state::{Account as SplAccount, AccountState, Mint as SplMint},
+            },
+        };
+
+        let (mut svm, data) = setup();
+        let delegate = Pubkey::new_unique();
+        create_vault_with_config(
+            &mut svm,
+            &data,
+            crate::state::VaultMintConfig {
+                decimals: 6,
+                transfer_fee: Some(crate::state::TransferFeeArgs {
+                    basis_points: 100,
+                    maximum_fee: 1_000,
+                }),
+                metadata: Some(crate::state::MetadataArgs {
+                    name: "Week1 Vault".to_string(),
+                    symbol: "WK1".to_string(),
+                    uri: "https://example.com/wk1.json".to_string(),
+                    additional_fields: vec![],
+                }),
+                interest_rate: Some(500),
+                default_frozen: true,
+                permanent_delegate: Some(delegate),
+                max_supply: None,
+                emission: None,
+            },
+        );
+
+        let account = svm.get_account(&data.mint.pubkey()).unwrap();
+        let mint = StateWithExtensions::<SplMint>::unpack(&account.data).unwrap();
+        assert_eq!(mint.base.decimals, 6);
+        assert_eq!(mint.base.freeze_authority, Some(data.vault_state).into());
+        let interest = mint.get_extension::<InterestBearingConfig>().unwrap();
+        assert_eq!(Option::<Pubkey>::from(interest.rate_authority), Some(data.vault_state));
+        let extensions = mint.get_extension_types().unwrap();
+        for extension in [
+            ExtensionType::TransferHook,
+            ExtensionType::TransferFeeConfig,
+            ExtensionType::MetadataPointer,
+            ExtensionType::TokenMetadata,
+            ExtensionType::InterestBearingConfig,
+            ExtensionType::DefaultAccountState,
+            ExtensionType::PermanentDelegate,
+        ] {
+            assert!(extensions.contains(&extension), "{:?} missing", extension);
+        }
+        let metadata = mint.get_variable_len_extension::<TokenMetadata>().unwrap();
+        assert_eq!(metadata.symbol, "WK1");
+
+        // shares follow the vault token's decimals
+        let account = svm.get_account(&data.share_mint).unwrap();
+        let share_mint = StateWithExtensions::<SplMint>::unpack(&account.data).unwrap();
+        assert_eq!(share_mint.base.decimals, 6);
+
+        // new accounts start frozen, except the vault's own
+        let account = svm.get_account(&data.vault_ata).unwrap();
+        let vault_ata = StateWithExtensions::<SplAccount>::unpack(&account.data).unwrap();
+        assert_eq!(vault_ata.base.state, AccountState::Initialized);
+
+        let (_user, user_ata) = new_user_with_ata(&mut svm, &data);
+        let account_state = |svm: &LiteSVM| {
+            let account = svm.get_account(&user_ata).unwrap();
+            StateWithExtensions::<SplAccount>::unpack(&account.data).unwrap().base.state
+        };
+        assert_eq!(account_state(&svm), AccountState::Frozen);
+
+        // the owner thaws and sets the rate through the vault, nobody else can
+        let thaw_ix = |owner: &Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ThawHolder {
+                owner: *owner,
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                holder_ata: user_ata,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ThawHolder {}.data(),
+        };
+        let stranger = Keypair::new();
+        svm.airdrop(&stranger.pubkey(), LAMPORTS_PER_SOL).unwrap();
+        assert!(!try_send(&mut svm, &[thaw_ix(&stranger.pubkey())], &[&stranger]));
+        send(&mut svm, &[thaw_ix(&data.admin.pubkey())], &[&data.admin]);
+        assert_eq!(account_state(&svm), AccountState::Initialized);
+
+        let rate_ix = |owner: &Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetInterestRate {
+                owner: *owner,
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetInterestRate { rate: 250 }.data(),
+        };
+        assert!(!try_send(&mut svm, &[rate_ix(&stranger.pubkey())], &[&stranger]));
+        send(&mut svm, &[rate_ix(&data.admin.pubkey())], &[&data.admin]);
+        let account = svm.get_account(&data.mint.pubkey()).unwrap();
+        let mint = StateWithExtensions::<SplMint>::unpack(&account.data).unwrap();
+        let interest = mint.get_extension::<InterestBearingConfig>().unwrap();
+        assert_eq!(i16::from(interest.current_rate), 250);
+
+        // mint_token thaws the ATA it creates frozen
+        let recipient = Pubkey::new_unique();
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &recipient)],
+            &[&data.admin],
+        );
+        mint_to_user(&mut svm, &data, &recipient, 100);
+        let recipient_ata = associated_token::get_associated_token_address_with_program_id(
+            &recipient,
+            &data.mint.pubkey(),
+            &data.token_program,
+        );
+        let account = svm.get_account(&recipient_ata).unwrap();
+        let account = StateWithExtensions::<SplAccount>::unpack(&account.data).unwrap();
+        assert_eq!(account.base.state, AccountState::Initialized);
+        assert_eq!(account.base.amount, 100);
+    }
+    #[test]
+    pub fn test_update_metadata() {
+        use {
+            anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata,
+            spl_token_2022::{
+                extension::{BaseStateWithExtensions, StateWithExtensions},
+                state::Mint as SplMint,
+            },
+        };
+
+        let (mut svm, data) = setup();
+        create_vault_with_config(
+            &mut svm,
+            &data,
+            crate::state::VaultMintConfig {
+                metadata: Some(crate::state::MetadataArgs {
+                    name: "Week1 Vault".to_string(),
+                    symbol: "WK1".to_string(),
+                    uri: "https://example.com/wk1.json".to_string(),
+                    additional_fields: vec![("category".to_string(), "vault".to_string())],
+                }),
+                ..Default::default()
+            },
+        );
+        let metadata = |svm: &LiteSVM| {
+            let account = svm.get_account(&data.mint.pubkey()).unwrap();
+            let mint = StateWithExtensions::<SplMint>::unpack(&account.data).unwrap();
+            (mint.get_variable_len_extension::<TokenMetadata>().unwrap(), account.data.len())
+        };
+        let (created, created_len) = metadata(&svm);
+        assert_eq!(created.update_authority, Some(data.vault_state).try_into().unwrap());
+        assert_eq!(created.additional_metadata, vec![("category".to_string(), "vault".to_string())]);
+
+        let update_ix = |owner: &Pubkey, field: &str, value: &str| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateMetadata {
+                owner: *owner,
+                vault_state: data.vault_state,
+                mint: data.mint.pubkey(),
+                system_program: data.system_program,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::UpdateMetadata {
+                field: field.to_string(),
+                value: value.to_string(),
+            }
+            .data(),
+        };
+
+        // only the vault owner may touch the metadata
+        let stranger = Keypair::new();
+        svm.airdrop(&stranger.pubkey(), LAMPORTS_PER_SOL).unwrap();
+        assert!(!try_send(
+            &mut svm,
+            &[update_ix(&stranger.pubkey(), "name", "Scam")],
+            &[&stranger]
+        ));
+
+        // growing fields are paid for by the owner
+        send(
+            &mut svm,
+            &[
+                update_ix(&data.admin.pubkey(), "uri", "https://example.com/metadata/wk1-v2.json"),
+                update_ix(&data.admin.pubkey(), "website", "https://example.com"),
+            ],
+            &[&data.admin],
+        );
+        let (updated, updated_len) = metadata(&svm);
+        assert_eq!(updated.name, "Week1 Vault");
+        assert_eq!(updated.uri, "https://example.com/metadata/wk1-v2.json");
+        assert_eq!(
+            updated.additional_metadata,
+            vec![
+                ("category".to_string(), "vault".to_string()),
+                ("website".to_string(), "https://example.com".to_string()),
+            ]
+        );
+        assert!(updated_len > created_len);
+        let account = svm.get_account(&data.mint.pubkey()).unwrap();
+        assert!(account.lamports >= svm.minimum_balance_for_rent_exemption(updated_len));
+    }
+    #[test]
+    pub fn test_transfer_fee_harvest() {
+        use spl_token_2022::{
+            extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+            state::Mint as SplMint,
+        };
+
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_config(
+            &mut svm,
+            &data,
+            crate::state::VaultMintConfig {
+                transfer_fee: Some(crate::state::TransferFeeArgs {
+                    basis_points: 100,
+                    maximum_fee: 1_000,
+                }),
+                ..Default::default()
+            },
+        );
+
+        let (user, _user_ata) = new_user_with_ata(&mut svm, &data);
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+            &[&data.admin],
+        );
+        mint_to_user(&mut svm, &data, &user.pubkey(), 1_000);
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user.pubkey(),
+                crate::instruction::Deposit { amount: 1_000 }.data(),
+            )],
+            &[&user],
+        );
+
+        // 1% is withheld on the vault ata, only the rest is booked and backs shares
+        assert_eq!(fetch_vault(&svm, &data).total_assets, 990);
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &user.pubkey())).amount, 990);
+        let user_share_ata = associated_token::get_associated_token_address_with_program_id(
+            &user.pubkey(),
+            &data.share_mint,
+            &data.token_program,
+        );
+        assert_eq!(token_balance(&svm, &user_share_ata), 990);
+
+        let treasury = Pubkey::find_program_address(
+            &[crate::TREASURY_SEED, data.vault_state.as_ref()],
+            &PROGRAM_ID,
+        )
+        .0;
+        let mut harvest_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::HarvestFees {
+                payer: user.pubkey(),
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                treasury,
+                system_program: data.system_program,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::HarvestFees {}.data(),
+        };
+        harvest_ix.accounts.push(AccountMeta::new(data.vault_ata, false));
+        // permissionless, the fees land in the vault's treasury
+        send(&mut svm, &[harvest_ix], &[&user]);
+        assert_eq!(token_balance(&svm, &treasury), 10);
+        assert_eq!(token_balance(&svm, &data.vault_ata), 990);
+
+        // only the owner pays out of the treasury, and the hook doesn't book it as a deposit
+        let (_receiver, receiver_ata) = new_user_with_ata(&mut svm, &data);
+        let withdraw_treasury_ix = |owner: &Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::WithdrawTreasury {
+                owner: *owner,
+                mint: data.mint.pubkey(),
+                hook_program_id: TRANSFER_HOOK_PROGRAM_ID,
+                vault_state: data.vault_state,
+                treasury,
+                destination: receiver_ata,
+                vault_entry: data.vault_entry,
+                whitelist: data.whitelist,
+                share_mint: data.share_mint,
+                extra_account_meta_list,
+                vault_program: PROGRAM_ID,
+                hook_authority: hook_authority(),
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::WithdrawTreasury { amount: 10 }.data(),
+        };
+        assert!(!try_send(&mut svm, &[withdraw_treasury_ix(&user.pubkey())], &[&user]));
+        send(&mut svm, &[withdraw_treasury_ix(&data.admin.pubkey())], &[&data.admin]);
+        assert_eq!(token_balance(&svm, &treasury), 0);
+        // 1% fee rounds up to 1, withheld on the receiver
+        assert_eq!(token_balance(&svm, &receiver_ata), 9);
+        assert_eq!(fetch_vault(&svm, &data).total_assets, 990);
+
+        // plain transfers into the treasury aren't deposits, the hook lets them through uncredited
+        mint_to_user(&mut svm, &data, &user.pubkey(), 100);
+        let mut to_treasury_ix =
+            hooked_transfer_ix(&data, extra_account_meta_list, &user, &data.vault_state, 100);
+        to_treasury_ix.accounts[2].pubkey = treasury;
+        send(&mut svm, &[to_treasury_ix], &[&user]);
+        assert_eq!(token_balance(&svm, &treasury), 99);
+        assert_eq!(fetch_vault(&svm, &data).total_assets, 990);
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &user.pubkey())).pending_shares, 0);
+
+        let set_fee_ix = |owner: &Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetTransferFee {
+                owner: *owner,
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetTransferFee {
+                basis_points: 50,
+                maximum_fee: 500,
+            }
+            .data(),
+        };
+        assert!(!try_send(&mut svm, &[set_fee_ix(&user.pubkey())], &[&user]));
+        send(&mut svm, &[set_fee_ix(&data.admin.pubkey())], &[&data.admin]);
+
+        let account = svm.get_account(&data.mint.pubkey()).unwrap();
+        let mint = StateWithExtensions::<SplMint>::unpack(&account.data).unwrap();
+        let fee_config = mint.get_extension::<TransferFeeConfig>().unwrap();
+        assert_eq!(u16::from(fee_config.newer_transfer_fee.transfer_fee_basis_points), 50);
+        assert_eq!(u64::from(fee_config.newer_transfer_fee.maximum_fee), 500);
+    }
+    #[test]
+    pub fn test_mint_token_limits() {
+        let (mut svm, data) = setup();
+        let start: i64 = svm.get_sysvar::<Clock>().unix_timestamp;
+        create_vault_with_config(
+            &mut svm,
+            &data,
+            crate::state::VaultMintConfig {
+                max_supply: Some(1_000),
+                emission: Some(crate::state::EmissionSchedule {
+                    start,
+                    per_second: 10,
+                }),
+                ..Default::default()
+            },
+        );
+
+        let (user, user_ata) = new_user_with_ata(&mut svm, &data);
+        // not whitelisted yet
+        assert!(!try_send(&mut svm, &[mint_token_ix(&data, &user.pubkey(), 1)], &[&data.admin]));
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+            &[&data.admin],
+        );
+        // nothing released at the start of the schedule
+        assert!(!try_send(&mut svm, &[mint_token_ix(&data, &user.pubkey(), 1)], &[&data.admin]));
+
+        let warp = |svm: &mut LiteSVM, seconds: i64| {
+            let mut clock: Clock = svm.get_sysvar();
+            clock.unix_timestamp = start + seconds;
+            svm.set_sysvar(&clock);
+            svm.expire_blockhash();
+        };
+
+        warp(&mut svm, 50);
+        assert!(!try_send(&mut svm, &[mint_token_ix(&data, &user.pubkey(), 501)], &[&data.admin]));
+        send(&mut svm, &[mint_token_ix(&data, &user.pubkey(), 500)], &[&data.admin]);
+
+        // the emission has caught up, the max supply still holds
+        warp(&mut svm, 200);
+        assert!(!try_send(&mut svm, &[mint_token_ix(&data, &user.pubkey(), 501)], &[&data.admin]));
+        send(&mut svm, &[mint_token_ix(&data, &user.pubkey(), 500)], &[&data.admin]);
+
+        assert_eq!(token_balance(&svm, &user_ata), 1_000);
+        assert_eq!(fetch_vault(&svm, &data).total_minted, 1_000);
+        // spl mint_to from the admin no longer works, the vault is the mint authority
+        let direct_mint_ix = spl_token_2022::instruction::mint_to_checked(
+            &data.token_program,
+            &data.mint.pubkey(),
+            &user_ata,
+            &data.admin.pubkey(),
+            &[],
+            1,
+            9,
+        )
+        .unwrap();
+        assert!(!try_send(&mut svm, &[direct_mint_ix], &[&data.admin]));
+    }
+    #[test]
+    pub fn test_membership_outlives_balance() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user, _user_ata) = new_user_with_ata(&mut svm, &data);
+        let (outsider, _outsider_ata) = new_user_with_ata(&mut svm, &data);
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+            &[&data.admin],
+        );
+        mint_to_user(&mut svm, &data, &user.pubkey(), 1_000);
+        let ix = |user: &Keypair, ix_data: Vec<u8>| {
+            deposit_withdraw_ix(&data, extra_account_meta_list, &user.pubkey(), ix_data)
+        };
+        let entry = entry_pda(&data.vault_state, &user.pubkey());
+
+        // full round trip, the entry survives at zero
+        send(&mut svm, &[ix(&user, crate::instruction::Deposit { amount: 400 }.data())], &[&user]);
+        send(&mut svm, &[ix(&user, crate::instruction::Withdraw { amount: 400 }.data())], &[&user]);
+        assert_eq!(fetch_entry(&svm, &entry).amount, 0);
+        assert!(fetch_entry(&svm, &entry).is_whitelisted);
+        send(&mut svm, &[ix(&user, crate::instruction::Deposit { amount: 300 }.data())], &[&user]);
+        assert_eq!(fetch_entry(&svm, &entry).amount, 300);
+
+        // unlisted users are turned away
+        assert!(!try_send(
+            &mut svm,
+            &[ix(&outsider, crate::instruction::Deposit { amount: 1 }.data())],
+            &[&outsider]
+        ));
+
+        // a removed user with a balance can still get out, but not back in
+        let remove_ix = |address: &Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::WhitelistOperations {
+                whitelist: data.whitelist,
+                whitelist_entry: entry_pda(&data.vault_state, address),
+                vault: data.vault_state,
+                share_mint: data.share_mint,
+                owner_share_ata: share_ata_of(&data, address),
+                admin: data.admin.pubkey(),
+                system_program: data.system_program,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::RemoveFromWhitelist {
+                address: *address,
+                _mint: data.mint.pubkey(),
+            }
+            .data(),
+        };
+        send(&mut svm, &[remove_ix(&user.pubkey())], &[&data.admin]);
+        assert!(!fetch_entry(&svm, &entry).is_whitelisted);
+        assert!(!try_send(
+            &mut svm,
+            &[ix(&user, crate::instruction::Deposit { amount: 1 }.data())],
+            &[&user]
+        ));
+        send(&mut svm, &[ix(&user, crate::instruction::Withdraw { amount: 300 }.data())], &[&user]);
+        assert_eq!(fetch_entry(&svm, &entry).amount, 0);
+
+        // shares sent over by another holder keep the emptied entry around for their withdrawal
+        let (giver, _giver_ata) = new_user_with_ata(&mut svm, &data);
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &giver.pubkey())],
+            &[&data.admin],
+        );
+        mint_to_user(&mut svm, &data, &giver.pubkey(), 1_000);
+        send(&mut svm, &[ix(&giver, crate::instruction::Deposit { amount: 100 }.data())], &[&giver]);
+        let share_transfer_ix = spl_token_2022::instruction::transfer_checked(
+            &data.token_program,
+            &share_ata_of(&data, &giver.pubkey()),
+            &data.share_mint,
+            &share_ata_of(&data, &user.pubkey()),
+            &giver.pubkey(),
+            &[],
+            50,
+            9,
+        )
+        .unwrap();
+        send(&mut svm, &[share_transfer_ix], &[&giver]);
+        send(&mut svm, &[remove_ix(&user.pubkey())], &[&data.admin]);
+        assert!(svm.get_account(&entry).is_some_and(|account| !account.data.is_empty()));
+        send(&mut svm, &[ix(&user, crate::instruction::Withdraw { amount: 50 }.data())], &[&user]);
+
+        // with nothing left the entry is closed
+        send(&mut svm, &[remove_ix(&user.pubkey())], &[&data.admin]);
+        assert!(svm.get_account(&entry).is_none_or(|account| account.data.is_empty()));
+
+        // deposits and withdrawals all run through the vault's own entry
+        assert!(!try_send(&mut svm, &[remove_ix(&data.vault_state)], &[&data.admin]));
+        assert!(fetch_entry(&svm, &entry_pda(&data.vault_state, &data.vault_state)).is_whitelisted);
+    }
+    #[test]
+    pub fn test_user_ata_must_belong_to_sender() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (attacker, attacker_ata) = new_user_with_ata(&mut svm, &data);
+        let (victim, victim_ata) = new_user_with_ata(&mut svm, &data);
+        for user in [&attacker, &victim] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+                &[&data.admin],
+            );
+            mint_to_user(&mut svm, &data, &user.pubkey(), 1_000);
+        }
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &attacker.pubkey(),
+                crate::instruction::Deposit { amount: 500 }.data(),
+            )],
+            &[&attacker],
+        );
+
+        // the attacker's own accounts with the victim's token account swapped in
+        let with_victim_ata = |ix_data: Vec<u8>| {
+            let mut ix = deposit_withdraw_ix(&data, extra_account_meta_list, &attacker.pubkey(), ix_data);
+            let user_ata = ix
+                .accounts
+                .iter_mut()
+                .find(|meta| meta.pubkey == attacker_ata)
+                .unwrap();
+            user_ata.pubkey = victim_ata;
+            ix
+        };
+        assert!(!try_send(
+            &mut svm,
+            &[with_victim_ata(crate::instruction::Withdraw { amount: 500 }.data())],
+            &[&attacker]
+        ));
+        assert!(!try_send(
+            &mut svm,
+            &[with_victim_ata(crate::instruction::Deposit { amount: 100 }.data())],
+            &[&attacker]
+        ));
+        assert_eq!(token_balance(&svm, &victim_ata), 1_000);
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &victim.pubkey())).amount, 0);
+
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &attacker.pubkey(),
+                crate::instruction::Withdraw { amount: 500 }.data(),
+            )],
+            &[&attacker],
+        );
+        assert_eq!(token_balance(&svm, &attacker_ata), 1_000);
+    }
+    #[test]
+    pub fn test_direct_transfer_is_credited() {
+        // credit_transfer only takes the signature of this hook's authority
+        assert_eq!(crate::TRANSFER_HOOK_PROGRAM_ID, TRANSFER_HOOK_PROGRAM_ID);
+
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user_a, _) = new_user_with_ata(&mut svm, &data);
+        let (user_b, _) = new_user_with_ata(&mut svm, &data);
+        for user in [&user_a, &user_b] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+                &[&data.admin],
+            );
+            mint_to_user(&mut svm, &data, &user.pubkey(), 1_000);
+        }
+        let share_ata = |user: &Keypair| {
+            associated_token::get_associated_token_address_with_program_id(
+                &user.pubkey(),
+                &data.share_mint,
+                &data.token_program,
+            )
+        };
+
+        // a plain token transfer into vault_ata is booked by the hook, shares wait to be minted
+        send(
+            &mut svm,
+            &[hooked_transfer_ix(&data, extra_account_meta_list, &user_a, &data.vault_state, 400)],
+            &[&user_a],
+        );
+        let entry_a = fetch_entry(&svm, &entry_pda(&data.vault_state, &user_a.pubkey()));
+        assert_eq!(entry_a.amount, 400);
+        assert_eq!(entry_a.pending_shares, 400);
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.total_assets, 400);
+        assert_eq!(vault.pending_shares, 400);
+
+        // pending shares count towards the price, B still gets 1:1
+        send(
+            &mut svm,
+            &[hooked_transfer_ix(&data, extra_account_meta_list, &user_b, &data.vault_state, 200)],
+            &[&user_b],
+        );
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &user_b.pubkey())).pending_shares, 200);
+
+        // the next deposit settles what the hook booked
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user_a.pubkey(),
+                crate::instruction::Deposit { amount: 100 }.data(),
+            )],
+            &[&user_a],
+        );
+        assert_eq!(token_balance(&svm, &share_ata(&user_a)), 500);
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &user_a.pubkey())).pending_shares, 0);
+
+        // or they can be claimed on their own
+        let claim_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ClaimShares {
+                sender: user_b.pubkey(),
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                whitelist_entry: entry_pda(&data.vault_state, &user_b.pubkey()),
+                share_mint: data.share_mint,
+                user_share_ata: share_ata(&user_b),
+                system_program: data.system_program,
+                token_program: data.token_program,
+                associated_token_program: data.ata_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ClaimShares {}.data(),
+        };
+        send(&mut svm, &[claim_ix.clone()], &[&user_b]);
+        assert_eq!(token_balance(&svm, &share_ata(&user_b)), 200);
+        assert_eq!(fetch_vault(&svm, &data).pending_shares, 0);
+        svm.expire_blockhash();
+        assert!(!try_send(&mut svm, &[claim_ix], &[&user_b]));
+    }
+    #[test]
+    pub fn test_whitelist_batch() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (depositor, _) = new_user_with_ata(&mut svm, &data);
+        let (share_holder, _) = new_user_with_ata(&mut svm, &data);
+        let others: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+        let batch_accounts = |addresses: &[Pubkey]| {
+            let mut accounts = crate::accounts::WhitelistBatch {
+                admin: data.admin.pubkey(),
+                vault: data.vault_state,
+                share_mint: data.share_mint,
+                system_program: data.system_program,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None);
+            accounts.extend(
+                addresses
+                    .iter()
+                    .map(|address| AccountMeta::new(entry_pda(&data.vault_state, address), false)),
+            );
+            accounts
+        };
+        let batch_ix = |addresses: &[Pubkey], ix_data: Vec<u8>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: batch_accounts(addresses),
+            data: ix_data,
+        };
+        let remove_many = |addresses: &[Pubkey]| {
+            let mut accounts = batch_accounts(addresses);
+            accounts.extend(
+                addresses
+                    .iter()
+                    .map(|address| AccountMeta::new_readonly(share_ata_of(&data, address), false)),
+            );
+            Instruction {
+                program_id: PROGRAM_ID,
+                accounts,
+                data: crate::instruction::RemoveMany { addresses: addresses.to_vec() }.data(),
+            }
+        };
+        let add_many = |addresses: &[Pubkey], cap: Option<u64>| {
+            let users = addresses
+                .iter()
+                .map(|address| crate::WhitelistBatchEntry { address: *address, cap, expires_at: None })
+                .collect();
+            batch_ix(addresses, crate::instruction::AddMany { users }.data())
+        };
+
+        let mut addresses = vec![depositor.pubkey(), share_holder.pubkey()];
+        addresses.extend(&others);
+        send(&mut svm, &[add_many(&addresses, Some(500))], &[&data.admin]);
+        for address in &addresses {
+            let entry = fetch_entry(&svm, &entry_pda(&data.vault_state, address));
+            assert_eq!(entry.owner, *address);
+            assert!(entry.is_whitelisted);
+            assert_eq!(entry.cap, Some(500));
+        }
+
+        // re-adding only touches the flag and a given cap, None keeps the one set
+        send(&mut svm, &[add_many(&addresses[..1], None)], &[&data.admin]);
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &depositor.pubkey())).cap, Some(500));
+        send(&mut svm, &[add_many(&addresses[..1], Some(800))], &[&data.admin]);
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &depositor.pubkey())).cap, Some(800));
+
+        let too_many: Vec<Pubkey> =
+            (0..=crate::MAX_WHITELIST_BATCH).map(|_| Pubkey::new_unique()).collect();
+        assert!(!try_send(&mut svm, &[add_many(&too_many, None)], &[&data.admin]));
+
+        mint_to_user(&mut svm, &data, &depositor.pubkey(), 1_000);
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &depositor.pubkey(),
+                crate::instruction::Deposit { amount: 300 }.data(),
+            )],
+            &[&depositor],
+        );
+
+        // the share holder's entry is empty, its shares were sent over by the depositor
+        let share_holder_ata = create_share_ata(&mut svm, &data, &share_holder);
+        let share_transfer_ix = spl_token_2022::instruction::transfer_checked(
+            &data.token_program,
+            &share_ata_of(&data, &depositor.pubkey()),
+            &data.share_mint,
+            &share_holder_ata,
+            &depositor.pubkey(),
+            &[],
+            100,
+            9,
+        )
+        .unwrap();
+        send(&mut svm, &[share_transfer_ix], &[&depositor]);
+
+        // the vault's own entry can't go, nor can share ATAs be swapped
+        let mut with_vault = addresses.clone();
+        with_vault.push(data.vault_state);
+        assert!(!try_send(&mut svm, &[remove_many(&with_vault)], &[&data.admin]));
+        let mut swapped_atas = remove_many(&addresses);
+        let holder_ata_index = swapped_atas.accounts.len() - addresses.len() + 1;
+        swapped_atas.accounts[holder_ata_index].pubkey = share_ata_of(&data, &others[0]);
+        assert!(!try_send(&mut svm, &[swapped_atas], &[&data.admin]));
+
+        // the depositor and the share holder keep disabled entries, the empty ones are closed and refunded
+        let admin_before = svm.get_account(&data.admin.pubkey()).unwrap().lamports;
+        let entry_rent = svm.get_account(&entry_pda(&data.vault_state, &others[0])).unwrap().lamports;
+        send(&mut svm, &[remove_many(&addresses)], &[&data.admin]);
+        let depositor_entry = fetch_entry(&svm, &entry_pda(&data.vault_state, &depositor.pubkey()));
+        assert!(!depositor_entry.is_whitelisted);
+        assert_eq!(depositor_entry.amount, 300);
+        let holder_entry = fetch_entry(&svm, &entry_pda(&data.vault_state, &share_holder.pubkey()));
+        assert!(!holder_entry.is_whitelisted);
+        assert_eq!(holder_entry.amount, 0);
+        for address in &others {
+            assert!(svm.get_account(&entry_pda(&data.vault_state, address)).is_none());
+        }
+        let admin_after = svm.get_account(&data.admin.pubkey()).unwrap().lamports;
+        assert_eq!(admin_after, admin_before + 2 * entry_rent - 5_000);
+    }
+    #[test]
+    pub fn test_whitelist_expiry() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let start = svm.get_sysvar::<Clock>().unix_timestamp;
+        let (user_a, _) = new_user_with_ata(&mut svm, &data);
+        let (user_b, _) = new_user_with_ata(&mut svm, &data);
+        let (cranker, _) = new_user_with_ata(&mut svm, &data);
+
+        // approvals can't start out expired
+        assert!(!try_send(
+            &mut svm,
+            &[whitelist_until_ix(&data, &data.admin.pubkey(), &user_a.pubkey(), Some(start))],
+            &[&data.admin]
+        ));
+        send(
+            &mut svm,
+            &[
+                whitelist_until_ix(&data, &data.admin.pubkey(), &user_a.pubkey(), Some(start + 100)),
+                add_to_whitelist_ix(&data, &data.admin.pubkey(), &user_b.pubkey()),
+            ],
+            &[&data.admin],
+        );
+        for user in [&user_a, &user_b] {
+            mint_to_user(&mut svm, &data, &user.pubkey(), 1_000);
+        }
+        let entry_a = entry_pda(&data.vault_state, &user_a.pubkey());
+        let ix = |ix_data: Vec<u8>| {
+            deposit_withdraw_ix(&data, extra_account_meta_list, &user_a.pubkey(), ix_data)
+        };
+        let prune_ix = |user: &Keypair| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::PruneExpired {
+                cranker: cranker.pubkey(),
+                owner: data.admin.pubkey(),
+                vault: data.vault_state,
+                whitelist_entry: entry_pda(&data.vault_state, &user.pubkey()),
+                share_mint: data.share_mint,
+                owner_share_ata: associated_token::get_associated_token_address_with_program_id(
+                    &user.pubkey(),
+                    &data.share_mint,
+                    &data.token_program,
+                ),
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::PruneExpired {}.data(),
+        };
+
+        send(&mut svm, &[ix(crate::instruction::Deposit { amount: 100 }.data())], &[&user_a]);
+        send(
+            &mut svm,
+            &[hooked_transfer_ix(&data, extra_account_meta_list, &user_a, &user_b.pubkey(), 10)],
+            &[&user_a],
+        );
+        assert!(!try_send(&mut svm, &[prune_ix(&user_a)], &[&cranker]));
+
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = start + 100;
+        svm.set_sysvar(&clock);
+        svm.expire_blockhash();
+
+        // expired owners can't send, but can still receive and withdraw
+        assert!(!try_send(
+            &mut svm,
+            &[hooked_transfer_ix(&data, extra_account_meta_list, &user_a, &user_b.pubkey(), 10)],
+            &[&user_a]
+        ));
+        send(
+            &mut svm,
+            &[hooked_transfer_ix(&data, extra_account_meta_list, &user_b, &user_a.pubkey(), 10)],
+            &[&user_b],
+        );
+
+        // a balance keeps the entry around until it is withdrawn
+        assert!(!try_send(&mut svm, &[prune_ix(&user_a)], &[&cranker]));
+        send(&mut svm, &[ix(crate::instruction::Withdraw { amount: 100 }.data())], &[&user_a]);
+
+        // so do shares handed over by someone else, the entry never saw them
+        send(
+            &mut svm,
+            &[deposit_withdraw_ix(
+                &data,
+                extra_account_meta_list,
+                &user_b.pubkey(),
+                crate::instruction::Deposit { amount: 50 }.data(),
+            )],
+            &[&user_b],
+        );
+        let share_ata = |user: &Keypair| {
+            associated_token::get_associated_token_address_with_program_id(
+                &user.pubkey(),
+                &data.share_mint,
+                &data.token_program,
+            )
+        };
+        let transfer_ix = spl_token_2022::instruction::transfer_checked(
+            &data.token_program,
+            &share_ata(&user_b),
+            &data.share_mint,
+            &share_ata(&user_a),
+            &user_b.pubkey(),
+            &[],
+            50,
+            9,
+        )
+        .unwrap();
+        send(&mut svm, &[transfer_ix], &[&user_b]);
+        assert_eq!(fetch_entry(&svm, &entry_a).amount, 0);
+        svm.expire_blockhash();
+        assert!(!try_send(&mut svm, &[prune_ix(&user_a)], &[&cranker]));
+        send(&mut svm, &[ix(crate::instruction::Withdraw { amount: 50 }.data())], &[&user_a]);
+
+        let admin_before = svm.get_account(&data.admin.pubkey()).unwrap().lamports;
+        let entry_rent = svm.get_account(&entry_a).unwrap().lamports;
+        send(&mut svm, &[prune_ix(&user_a)], &[&cranker]);
+        assert!(svm.get_account(&entry_a).is_none());
+        assert_eq!(
+            svm.get_account(&data.admin.pubkey()).unwrap().lamports,
+            admin_before + entry_rent
+        );
+
+        // entries without an expiry are never pruned
+        assert!(!try_send(&mut svm, &[prune_ix(&user_b)], &[&cranker]));
+    }
+    #[test]
+    pub fn test_merkle_whitelist() {
+        let (mut svm, data) = setup();
+        create_vault_with_hook(&mut svm, &data);
+
+        let (user, _) = new_user_with_ata(&mut svm, &data);
+        let (outsider, _) = new_user_with_ata(&mut svm, &data);
+        let mut csv = String::from("address\n");
+        csv.push_str(&format!("{},airdrop\n", user.pubkey()));
+        for _ in 0..4 {
+            csv.push_str(&format!("{}\n", Pubkey::new_unique()));
+        }
+        let tree = crate::merkle::MerkleTree::from_csv(&csv).unwrap();
+        assert_eq!(tree.addresses().len(), 5);
+
+        let set_root_ix = |owner: &Keypair, root: Option<[u8; 32]>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultConfig {
+                owner: owner.pubkey(),
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetWhitelistRoot { root }.data(),
+        };
+        let claim_ix = |user: &Keypair, proof: Vec<[u8; 32]>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ClaimWhitelist {
+                user: user.pubkey(),
+                vault: data.vault_state,
+                whitelist_entry: entry_pda(&data.vault_state, &user.pubkey()),
+                system_program: data.system_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ClaimWhitelist { proof }.data(),
+        };
+        let proof = tree.proof(&user.pubkey()).unwrap();
+
+        // nothing to claim against before the admin publishes a root
+        assert!(!try_send(&mut svm, &[claim_ix(&user, proof.clone())], &[&user]));
+        send(&mut svm, &[set_root_ix(&data.admin, Some(tree.root()))], &[&data.admin]);
+
+        // someone else's proof doesn't work for the outsider
+        assert!(tree.proof(&outsider.pubkey()).is_none());
+        assert!(!try_send(&mut svm, &[claim_ix(&outsider, proof.clone())], &[&outsider]));
+
+        send(&mut svm, &[claim_ix(&user, proof.clone())], &[&user]);
+        let entry = fetch_entry(&svm, &entry_pda(&data.vault_state, &user.pubkey()));
+        assert!(entry.is_whitelisted);
+        assert_eq!(entry.owner, user.pubkey());
+        svm.expire_blockhash();
+        assert!(!try_send(&mut svm, &[claim_ix(&user, proof)], &[&user]));
+
+        // rotating the root lets the next list in
+        let rotated = crate::merkle::MerkleTree::new(vec![outsider.pubkey(), Pubkey::new_unique()]);
+        send(&mut svm, &[set_root_ix(&data.admin, Some(rotated.root()))], &[&data.admin]);
+        send(
+            &mut svm,
+            &[claim_ix(&outsider, rotated.proof(&outsider.pubkey()).unwrap())],
+            &[&outsider],
+        );
+        assert!(fetch_entry(&svm, &entry_pda(&data.vault_state, &outsider.pubkey())).is_whitelisted);
+
+        // only the owner sets the root
+        assert!(!try_send(&mut svm, &[set_root_ix(&outsider, None)], &[&outsider]));
+    }
+    fn reconcile_ix(data: &ReusableData, authority: &Keypair, pause_on_mismatch: bool) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Reconcile {
+                authority: authority.pubkey(),
+                mint: data.mint.pubkey(),
+                vault_state: data.vault_state,
+                vault_ata: data.vault_ata,
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Reconcile { pause_on_mismatch }.data(),
+        }
+    }
+
+    #[test]
+    pub fn test_reconcile_pauses_on_mismatch() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (user, _) = new_user_with_ata(&mut svm, &data);
+        send(
+            &mut svm,
+            &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+            &[&data.admin],
+        );
+        mint_to_user(&mut svm, &data, &user.pubkey(), 1_000);
+        let ix = |ix_data: Vec<u8>| {
+            deposit_withdraw_ix(&data, extra_account_meta_list, &user.pubkey(), ix_data)
+        };
+        send(&mut svm, &[ix(crate::instruction::Deposit { amount: 500 }.data())], &[&user]);
+
+        // anyone can record, the books match after a deposit
+        send(&mut svm, &[reconcile_ix(&data, &user, false)], &[&user]);
+        let last = fetch_vault(&svm, &data).last_reconciliation.unwrap();
+        assert_eq!((last.liabilities, last.holdings), (500, 500));
+
+        // a direct transfer into vault_ata is booked by the hook, it can't open a gap
+        send(
+            &mut svm,
+            &[hooked_transfer_ix(&data, extra_account_meta_list, &user, &data.vault_state, 100)],
+            &[&user],
+        );
+        svm.expire_blockhash();
+        send(&mut svm, &[reconcile_ix(&data, &user, false)], &[&user]);
+        let last = fetch_vault(&svm, &data).last_reconciliation.unwrap();
+        assert_eq!((last.liabilities, last.holdings), (600, 600));
+        assert!(last.is_balanced());
+
+        // tokens minted straight into vault_ata never went through deposit or the hook
+        mint_to_user(&mut svm, &data, &data.vault_state, 100);
+
+        svm.expire_blockhash();
+        assert!(!try_send(&mut svm, &[reconcile_ix(&data, &user, true)], &[&user]));
+        send(&mut svm, &[reconcile_ix(&data, &user, false)], &[&user]);
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.last_reconciliation.unwrap().holdings, 700);
+        assert!(!vault.last_reconciliation.unwrap().is_balanced());
+        assert!(!vault.paused_withdrawals);
+
+        svm.expire_blockhash();
+        send(&mut svm, &[reconcile_ix(&data, &data.admin, true)], &[&data.admin]);
+        assert!(fetch_vault(&svm, &data).paused_withdrawals);
+        assert!(!try_send(&mut svm, &[ix(crate::instruction::Withdraw { amount: 100 }.data())], &[&user]));
+    }
+    #[test]
+    pub fn test_flash_loan() {
+        let (mut svm, data) = setup();
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (depositor, depositor_ata) = new_user_with_ata(&mut svm, &data);
+        let (borrower, borrower_ata) = new_user_with_ata(&mut svm, &data);
+        for user in [&depositor, &borrower] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &user.pubkey())],
+                &[&data.admin],
+            );
+        }
+        mint_to_user(&mut svm, &data, &depositor.pubkey(), 1_000);
+        mint_to_user(&mut svm, &data, &borrower.pubkey(), 100);
+        let ix = |ix_data: Vec<u8>| {
+            deposit_withdraw_ix(&data, extra_account_meta_list, &depositor.pubkey(), ix_data)
+        };
+        send(&mut svm, &[ix(crate::instruction::Deposit { amount: 1_000 }.data())], &[&depositor]);
+
+        let flash_ix = |ix_data: Vec<u8>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::FlashLoan {
+                borrower: borrower.pubkey(),
+                mint: data.mint.pubkey(),
+                hook_program_id: TRANSFER_HOOK_PROGRAM_ID,
+                vault_state: data.vault_state,
+                vault_ata: data.vault_ata,
+                borrower_ata,
+                vault_entry: data.vault_entry,
+                borrower_entry: entry_pda(&data.vault_state, &borrower.pubkey()),
+                whitelist: data.whitelist,
+                share_mint: data.share_mint,
+                extra_account_meta_list,
+                vault_program: PROGRAM_ID,
+                instructions_sysvar: INSTRUCTIONS_SYSVAR_ID,
+                hook_authority: hook_authority(),
+                token_program: data.token_program,
+            }
+            .to_account_metas(None),
+            data: ix_data,
+        };
+        let borrow = |amount: u64| flash_ix(crate::instruction::FlashBorrow { amount }.data());
+        let repay = || flash_ix(crate::instruction::FlashRepay {}.data());
+
+        // off until the owner sets a fee
+        assert!(!try_send(&mut svm, &[borrow(400), repay()], &[&borrower]));
+        let set_fee_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VaultConfig {
+                owner: data.admin.pubkey(),
+                vault_state: data.vault_state,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetFlashFee { fee_bps: Some(50) }.data(),
+        };
+        send(&mut svm, &[set_fee_ix], &[&data.admin]);
+
+        // a borrow has to be followed by its repay, and a repay needs a borrow
+        assert!(!try_send(&mut svm, &[borrow(400)], &[&borrower]));
+        assert!(!try_send(&mut svm, &[repay()], &[&borrower]));
+
+        // 0.5% of 400, rounded up
+        svm.expire_blockhash();
+        send(&mut svm, &[borrow(400), repay()], &[&borrower]);
+        assert_eq!(token_balance(&svm, &borrower_ata), 98);
+        assert_eq!(token_balance(&svm, &data.vault_ata), 1_002);
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.total_assets, 1_002);
+        assert_eq!(vault.flash_loan, None);
+
+        // the fee went to the depositor's shares
+        send(&mut svm, &[ix(crate::instruction::Withdraw { amount: 1_000 }.data())], &[&depositor]);
+        assert_eq!(token_balance(&svm, &depositor_ata), 1_002);
+    }
+    #[test]
+    pub fn test_escrow_adapter_matches_deposit() {
+        use anchor_escrow::vault_interface::{VaultProgram, Week1Vault, VAULT_PROGRAMS};
+
+        // the escrow hand-copies these, see Week1Vault
+        assert_eq!(Week1Vault.program_id(), PROGRAM_ID);
+        assert!(VAULT_PROGRAMS.iter().any(|vault_program| vault_program.program_id() == PROGRAM_ID));
+        assert_eq!(
+            Week1Vault.deposit_data(1_000),
+            crate::instruction::Deposit { amount: 1_000 }.data()
+        );
+    }
+
+    // plain Token-2022 mint, `owner` holds `amount` of it
+    fn plain_mint_with_balance(svm: &mut LiteSVM, owner: &Keypair, amount: u64) -> Pubkey {
+        let mint = Keypair::new();
+        let token_program = spl_token_2022::ID;
+        let space = spl_token_2022::state::Mint::LEN;
+        let ixs = [
+            anchor_lang::solana_program::system_instruction::create_account(
+                &owner.pubkey(),
+                &mint.pubkey(),
+                svm.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &token_program,
+            ),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint.pubkey(),
+                &owner.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &owner.pubkey(),
+                &owner.pubkey(),
+                &mint.pubkey(),
+                &token_program,
+            ),
+            spl_token_2022::instruction::mint_to(
+                &token_program,
+                &mint.pubkey(),
+                &associated_token::get_associated_token_address_with_program_id(
+                    &owner.pubkey(),
+                    &mint.pubkey(),
+                    &token_program,
+                ),
+                &owner.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ];
+        send(svm, &ixs, &[owner, &mint]);
+        mint.pubkey()
+    }
+
+    // hook accounts for a transfer of the vault mint out of `source_owner`'s account
+    fn hook_metas(data: &ReusableData, extra_account_meta_list: Pubkey, source_owner: &Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(extra_account_meta_list, false),
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+            AccountMeta::new(data.vault_state, false),
+            AccountMeta::new(entry_pda(&data.vault_state, source_owner), false),
+            AccountMeta::new_readonly(data.whitelist, false),
+            AccountMeta::new_readonly(data.share_mint, false),
+            AccountMeta::new_readonly(hook_authority(), false),
+            AccountMeta::new_readonly(TRANSFER_HOOK_PROGRAM_ID, false),
+        ]
+    }
+
+    #[test]
+    pub fn test_deposit_through_cpi() {
+        let (mut svm, data) = setup();
+        let escrow_so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../../../escrow-litesvm/target/deploy/anchor_escrow.so");
+        let escrow_program_data =
+            std::fs::read(escrow_so_path).expect("Failed to read escrow SO file");
+        svm.add_program(anchor_escrow::ID, &escrow_program_data)
+            .expect("failed to add escrow program");
+        let extra_account_meta_list = create_vault_with_hook(&mut svm, &data);
+
+        let (maker, maker_ata_a) = new_user_with_ata(&mut svm, &data);
+        let taker = Keypair::new();
+        svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+        let seed = 7u64;
+        let escrow = Pubkey::find_program_address(
+            &[b"escrow", maker.pubkey().as_ref(), &seed.to_le_bytes()],
+            &anchor_escrow::ID,
+        )
+        .0;
+        for address in [maker.pubkey(), taker.pubkey(), escrow] {
+            send(
+                &mut svm,
+                &[add_to_whitelist_ix(&data, &data.admin.pubkey(), &address)],
+                &[&data.admin],
+            );
+        }
+        mint_to_user(&mut svm, &data, &maker.pubkey(), 1_000);
+
+        // the taker pays in a plain Token-2022 mint, the escrow runs a single token program
+        let mint_b = plain_mint_with_balance(&mut svm, &taker, 500);
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            associated_token::get_associated_token_address_with_program_id(owner, mint, &data.token_program)
+        };
+        let escrow_vault = ata(&escrow, &data.mint.pubkey());
+
+        let mut make_ix = Instruction {
+            program_id: anchor_escrow::ID,
+            accounts: anchor_escrow::accounts::Make {
+                maker: maker.pubkey(),
+                mint_a: data.mint.pubkey(),
+                mint_b,
+                maker_ata_a,
+                escrow,
+                vault: escrow_vault,
+                associated_token_program: data.ata_program,
+                token_program: data.token_program,
+                system_program: data.system_program,
+            }
+            .to_account_metas(None),
+            data: anchor_escrow::instruction::Make { seed, deposit: 600, receive: 500 }.data(),
+        };
+        make_ix.accounts.extend(hook_metas(&data, extra_account_meta_list, &maker.pubkey()));
+        send(&mut svm, &[make_ix], &[&maker]);
+
+        // take_into_vault CPIs into deposit, whose transfer into vault_ata runs the hook under the escrow
+        let deposit_metas = deposit_withdraw_ix(
+            &data,
+            extra_account_meta_list,
+            &taker.pubkey(),
+            crate::instruction::Deposit { amount: 600 }.data(),
+        )
+        .accounts;
+        let mut take_ix = Instruction {
+            program_id: anchor_escrow::ID,
+            accounts: anchor_escrow::accounts::Take {
+                taker: taker.pubkey(),
+                maker: maker.pubkey(),
+                mint_a: data.mint.pubkey(),
+                mint_b,
+                collection: None,
+                taker_ata_a: ata(&taker.pubkey(), &data.mint.pubkey()),
+                taker_ata_b: ata(&taker.pubkey(), &mint_b),
+                maker_ata_b: ata(&maker.pubkey(), &mint_b),
+                escrow,
+                vault: escrow_vault,
+                associated_token_program: data.ata_program,
+                token_program: data.token_program,
+                token_program_b: data.token_program,
+                system_program: data.system_program,
+            }
+            .to_account_metas(None),
+            data: anchor_escrow::instruction::TakeIntoVault {
+                deposit_accounts: deposit_metas.len() as u8,
+            }
+            .data(),
+        };
+        take_ix.accounts.push(AccountMeta::new_readonly(PROGRAM_ID, false));
+        take_ix.accounts.extend(deposit_metas);
+        take_ix.accounts.extend(hook_metas(&data, extra_account_meta_list, &escrow));
+        send(&mut svm, &[take_ix], &[&taker]);
+
+        // booked once, by deposit, the hook left the transfer alone
+        let share_ata = ata(&taker.pubkey(), &data.share_mint);
+        assert_eq!(token_balance(&svm, &share_ata), 600);
+        assert_eq!(token_balance(&svm, &data.vault_ata), 600);
+        let entry = fetch_entry(&svm, &entry_pda(&data.vault_state, &taker.pubkey()));
+        assert_eq!(entry.amount, 600);
+        assert_eq!(entry.pending_shares, 0);
+        let vault = fetch_vault(&svm, &data);
+        assert_eq!(vault.total_assets, 600);
+        assert_eq!(vault.pending_shares, 0);
+        assert!(!vault.vault_transfer);
+
+        // the flag is cleared again, a plain transfer into the vault is still credited
+        send(
+            &mut svm,
+            &[hooked_transfer_ix(&data, extra_account_meta_list, &maker, &data.vault_state, 100)],
+            &[&maker],
+        );
+        assert_eq!(fetch_entry(&svm, &entry_pda(&data.vault_state, &maker.pubkey())).pending_shares, 100);
+        assert_eq!(fetch_vault(&svm, &data).total_assets, 700);
     }
 }